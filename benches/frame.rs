@@ -0,0 +1,114 @@
+//! Frame time of preparing a frame of a large image with each filter of the viewer.
+//!
+//! A frame is cropped, resampled, adjusted and blended over a checkerboard.
+//! `before` is the single threaded `resize_exact` of image,
+//! `linear` is the default linear light path on all threads.
+//!
+//! Run with `cargo bench --bench frame [-- WIDTHxHEIGHT]`.
+use std::{
+    env,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use image::{
+    imageops::FilterType,
+    DynamicImage,
+    RgbaImage,
+};
+use term_lattice::types::Rgb;
+use timg::{
+    available_threads,
+    composite,
+    par_rows,
+    resize_linear,
+    resize_parallel,
+    rgba_to_rgb,
+    rgba_to_rgb_linear,
+    Adjustments,
+    Checker,
+    Rgba,
+};
+
+/// 与查看器中的滤波器列表相同
+const FILTERS: &[FilterType] = &[
+    FilterType::Nearest, FilterType::Triangle,
+    FilterType::CatmullRom, FilterType::Gaussian,
+    FilterType::Lanczos3
+];
+/// 输出的帧大小, 约为较大的终端
+const FRAME_SIZE: [u32; 2] = [400, 200];
+const RUNS: usize = 3;
+/// 调整使每个像素都经过色调表与颜色矩阵
+const ADJUSTMENTS: Adjustments = Adjustments {
+    brightness: 0.1,
+    contrast: 1.2,
+    gamma: 1.0,
+    hue: 0.0,
+    saturation: 1.3,
+};
+
+/// 多次运行中最快的一次
+fn measure<F: FnMut()>(mut f: F) -> Duration {
+    (0..RUNS).map(|_| {
+        let start = Instant::now();
+        f();
+        start.elapsed()
+    }).min().unwrap()
+}
+
+/// 准备一帧, `resize` 裁剪出的视区缩放至帧大小
+fn prepare<R>(img: &DynamicImage,
+              threads: usize,
+              blend: fn(Rgba, Rgb) -> Rgb,
+              resize: R) -> Vec<Rgb>
+where R: FnOnce(&DynamicImage) -> DynamicImage,
+{
+    let view = img.crop_imm(0, 0, img.width(), img.height());
+    let mut rgba = resize(&view).into_rgba8();
+    par_rows(&mut rgba, threads, |_, band| ADJUSTMENTS.apply(band));
+    let checker = Checker::default();
+    composite(&rgba, threads, blend, |x, y| checker.color_at(x, y))
+}
+
+fn main() {
+    let [width, height] = env::args().skip(1)
+        .find(|arg| ! arg.starts_with('-'))
+        .map(|size| {
+            let (w, h) = size.split_once('x').expect("size: WIDTHxHEIGHT");
+            [w, h].map(|x| x.parse().expect("size: WIDTHxHEIGHT"))
+        })
+        .unwrap_or([8000, 6000]);
+    let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+        image::Rgba([(x ^ y) as u8, (x * 3) as u8, (y * 7) as u8, (x + y) as u8])
+    }));
+    let threads = available_threads();
+    let [w, h] = FRAME_SIZE;
+    println!("{}x{} -> {}x{}, {} threads, best of {} runs",
+             width, height, w, h, threads, RUNS);
+    println!("{:<12}{:>12}{:>12}{:>12}{:>12}",
+             "filter", "before", "1 thread", format!("{} threads", threads), "linear");
+    let ms = |d: Duration| format!("{:.1}ms", d.as_secs_f64() * 1000.0);
+    for &filter in FILTERS {
+        let before = measure(|| {
+            prepare(&img, 1, rgba_to_rgb, |img| img.resize_exact(w, h, filter));
+        });
+        let single = measure(|| {
+            prepare(&img, 1, rgba_to_rgb,
+                    |img| resize_parallel(img, w, h, filter, 1));
+        });
+        let multi = measure(|| {
+            prepare(&img, threads, rgba_to_rgb,
+                    |img| resize_parallel(img, w, h, filter, threads));
+        });
+        let linear = measure(|| {
+            prepare(&img, threads, rgba_to_rgb_linear,
+                    |img| resize_linear(img, w, h, filter));
+        });
+        println!("{:<12}{:>12}{:>12}{:>12}{:>12}",
+                 format!("{:?}", filter),
+                 ms(before), ms(single), ms(multi), ms(linear));
+    }
+}
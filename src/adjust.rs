@@ -0,0 +1,259 @@
+use std::ops::DerefMut;
+
+use image::ImageBuffer;
+
+use super::*;
+
+/// 可调整的图片参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Adjustment {
+    Brightness,
+    Contrast,
+    Gamma,
+    Hue,
+    Saturation,
+}
+impl Adjustment {
+    pub const ALL: [Self; 5] = [
+        Self::Brightness, Self::Contrast, Self::Gamma,
+        Self::Hue, Self::Saturation,
+    ];
+    /// 状态行中使用的简称
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Self::Brightness => "Bri",
+            Self::Contrast => "Con",
+            Self::Gamma => "Gam",
+            Self::Hue => "Hue",
+            Self::Saturation => "Sat",
+        }
+    }
+    /// 在 [`Self::ALL`] 中循环获取下一个
+    /// # Examples
+    /// ```
+    /// use timg::Adjustment;
+    /// assert_eq!(Adjustment::Brightness.next(), Adjustment::Contrast);
+    /// assert_eq!(Adjustment::Saturation.next(), Adjustment::Brightness);
+    /// assert_eq!(Adjustment::Brightness.prev(), Adjustment::Saturation);
+    /// ```
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|&x| x == self).unwrap();
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+    pub fn prev(self) -> Self {
+        let idx = Self::ALL.iter().position(|&x| x == self).unwrap();
+        Self::ALL[(idx + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+/// Brightness, contrast, gamma, hue and saturation adjustments.
+///
+/// Applied on gamma-encoded values in the order of gamma, contrast,
+/// brightness, then hue rotation and saturation as CSS filters do.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Adjustments {
+    /// 加在各个通道上, 范围约为 -1 到 1
+    pub brightness: Float,
+    /// 以 0.5 为中心的缩放倍数
+    pub contrast: Float,
+    pub gamma: Float,
+    /// 色相旋转的角度
+    pub hue: Float,
+    /// 饱和度倍数, 为 0 时为灰度
+    pub saturation: Float,
+}
+impl Adjustments {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// 是否不会改变图片
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+    /// 获取一项的值
+    pub fn get(&self, which: Adjustment) -> Float {
+        match which {
+            Adjustment::Brightness => self.brightness,
+            Adjustment::Contrast => self.contrast,
+            Adjustment::Gamma => self.gamma,
+            Adjustment::Hue => self.hue,
+            Adjustment::Saturation => self.saturation,
+        }
+    }
+    /// 将一项调整 `steps` 步, 负数则减小
+    /// # Examples
+    /// ```
+    /// use timg::{Adjustment, Adjustments};
+    /// let mut adj = Adjustments::new();
+    /// adj.step(Adjustment::Brightness, 2);
+    /// assert!((adj.brightness - 0.1).abs() < 1e-9);
+    /// adj.step(Adjustment::Hue, -1);
+    /// assert_eq!(adj.hue, 345.0);
+    /// adj.step(Adjustment::Saturation, -20);
+    /// assert_eq!(adj.saturation, 0.0);
+    /// ```
+    pub fn step(&mut self, which: Adjustment, steps: i32) {
+        let steps = steps as Float;
+        match which {
+            Adjustment::Brightness => {
+                self.brightness = (self.brightness + steps * 0.05).clamp(-1.0, 1.0)
+            },
+            Adjustment::Contrast => {
+                self.contrast = (self.contrast + steps * 0.1).clamp(0.0, 10.0)
+            },
+            Adjustment::Gamma => {
+                self.gamma = (self.gamma + steps * 0.1).clamp(0.1, 10.0)
+            },
+            Adjustment::Hue => {
+                self.hue = (self.hue + steps * 15.0).rem_euclid(360.0)
+            },
+            Adjustment::Saturation => {
+                self.saturation = (self.saturation + steps * 0.1).clamp(0.0, 10.0)
+            },
+        }
+        // 避免浮点误差使其无法回到默认值
+        let default = Self::default();
+        macro_rules! snap {
+            ( $( $name:ident ),* ) => {
+                $(
+                    if (self.$name - default.$name).abs() < 1e-9 {
+                        self.$name = default.$name
+                    }
+                )*
+            };
+        }
+        snap!(brightness, contrast, gamma, hue, saturation);
+    }
+    /// 格式化一项的值
+    /// # Examples
+    /// ```
+    /// use timg::{Adjustment, Adjustments};
+    /// let adj = Adjustments::new();
+    /// assert_eq!(adj.fmt_value(Adjustment::Brightness), "Bri+0.00");
+    /// assert_eq!(adj.fmt_value(Adjustment::Hue), "Hue0");
+    /// assert_eq!(adj.fmt_value(Adjustment::Gamma), "Gam1.00");
+    /// ```
+    pub fn fmt_value(&self, which: Adjustment) -> String {
+        let value = self.get(which);
+        match which {
+            Adjustment::Brightness
+                => format!("{}{:+.2}", which.short_name(), value),
+            Adjustment::Hue => format!("{}{:.0}", which.short_name(), value),
+            _ => format!("{}{:.2}", which.short_name(), value),
+        }
+    }
+    /// 亮度, 对比度, 伽马的查找表
+    fn tone_table(&self) -> [u8; 256] {
+        let mut table = [0; 256];
+        for (i, x) in table.iter_mut().enumerate() {
+            let mut value = (i as Float / 255.0).powf(1.0 / self.gamma);
+            value = (value - 0.5) * self.contrast + 0.5 + self.brightness;
+            *x = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        table
+    }
+    /// 色相旋转与饱和度的颜色矩阵, 与 CSS 的 `hue-rotate` `saturate` 一致
+    fn color_matrix(&self) -> [[Float; 3]; 3] {
+        let (sin, cos) = self.hue.to_radians().sin_cos();
+        let hue = [
+            [0.213 + cos * 0.787 - sin * 0.213,
+             0.715 - cos * 0.715 - sin * 0.715,
+             0.072 - cos * 0.072 + sin * 0.928],
+            [0.213 - cos * 0.213 + sin * 0.143,
+             0.715 + cos * 0.285 + sin * 0.140,
+             0.072 - cos * 0.072 - sin * 0.283],
+            [0.213 - cos * 0.213 - sin * 0.787,
+             0.715 - cos * 0.715 + sin * 0.715,
+             0.072 + cos * 0.928 + sin * 0.072],
+        ];
+        let s = self.saturation;
+        let saturate = [
+            [0.213 + 0.787 * s, 0.715 - 0.715 * s, 0.072 - 0.072 * s],
+            [0.213 - 0.213 * s, 0.715 + 0.285 * s, 0.072 - 0.072 * s],
+            [0.213 - 0.213 * s, 0.715 - 0.715 * s, 0.072 + 0.928 * s],
+        ];
+        let mut res = [[0.0; 3]; 3];
+        for (i, row) in res.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = (0..3).map(|k| saturate[i][k] * hue[k][j]).sum();
+            }
+        }
+        res
+    }
+    /// 调整图片, 透明度不变
+    pub fn apply<C>(&self, img: &mut ImageBuffer<image::Rgba<u8>, C>)
+        where C: DerefMut<Target = [u8]>
+    {
+        if self.is_identity() {
+            return;
+        }
+        let table = self.tone_table();
+        let use_matrix = self.hue != 0.0 || self.saturation != 1.0;
+        let matrix = self.color_matrix();
+        for pixel in img.pixels_mut() {
+            let [r, g, b, _] = &mut pixel.0;
+            let mut rgb = [table[*r as usize], table[*g as usize], table[*b as usize]];
+            if use_matrix {
+                let src = rgb.map(|x| x as Float);
+                for (x, row) in rgb.iter_mut().zip(matrix) {
+                    let value: Float = row.iter().zip(src).map(|(a, b)| a * b).sum();
+                    *x = value.round().clamp(0.0, 255.0) as u8;
+                }
+            }
+            [*r, *g, *b] = rgb;
+        }
+    }
+}
+impl Default for Adjustments {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            gamma: 1.0,
+            hue: 0.0,
+            saturation: 1.0,
+        }
+    }
+}
+
+#[test]
+fn adjustments_test() {
+    use image::{Rgba, RgbaImage};
+
+    let src = RgbaImage::from_fn(16, 16, |x, y| {
+        Rgba([(x * 16) as u8, (y * 16) as u8, (x * y) as u8, 200])
+    });
+    let apply = |adj: Adjustments| {
+        let mut img = src.clone();
+        adj.apply(&mut img);
+        img
+    };
+
+    let mut adj = Adjustments::new();
+    assert_eq!(apply(adj), src);
+    adj.step(Adjustment::Hue, 24);
+    assert_eq!(adj.hue, 0.0);
+    assert!(adj.is_identity());
+
+    adj.saturation = 0.0;
+    for pixel in apply(adj).pixels() {
+        let [r, g, b, a] = pixel.0;
+        assert!(r.abs_diff(g) <= 1 && g.abs_diff(b) <= 1);
+        assert_eq!(a, 200);
+    }
+
+    let adj = Adjustments { brightness: 1.0, ..Adjustments::new() };
+    assert!(apply(adj).pixels().all(|p| p.0[..3] == [255; 3]));
+
+    let adj = Adjustments { contrast: 0.0, ..Adjustments::new() };
+    assert!(apply(adj).pixels().all(|p| p.0[..3] == [128; 3]));
+
+    let adj = Adjustments { gamma: 2.0, ..Adjustments::new() };
+    assert_eq!(apply(adj).get_pixel(4, 4).0, [128, 128, 64, 200]);
+
+    let adj = Adjustments { hue: 120.0, ..Adjustments::new() };
+    let mut img = RgbaImage::from_pixel(1, 1, Rgba([255, 0, 0, 255]));
+    adj.apply(&mut img);
+    let [r, g, b, _] = img.get_pixel(0, 0).0;
+    assert!(g > r && g > b);
+}
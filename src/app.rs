@@ -5,11 +5,40 @@ pub const DEFAULT_TERM_SIZE: [SizeType; 2] = [80, 80];
 
 use ::{
     clap::ArgMatches,
-    image::imageops::FilterType,
+    image::{
+        imageops::{
+            self,
+            FilterType
+        },
+        DynamicImage,
+        RgbaImage
+    },
     raw_tty::IntoRawMode,
-    std::io::{
-        stdin,
-        Read
+    std::{
+        fs::{
+            File,
+            OpenOptions
+        },
+        io::{
+            self,
+            Read
+        },
+        path::PathBuf,
+        os::unix::io::{
+            AsRawFd,
+            RawFd
+        },
+        sync::mpsc::{
+            self,
+            Receiver,
+            TryRecvError
+        },
+        mem,
+        thread,
+        time::{
+            Duration,
+            Instant
+        }
     },
     term_lattice::{
         types::Rgb,
@@ -18,51 +47,242 @@ use ::{
     },
     term_size::dimensions,
     timg::{
-        base16_to_unum,
-        get_scale,
-        num_to_rgb,
+        available_threads,
+        composite,
+        cell_pixel_aspect,
+        fmt_pixel,
+        color_to_rgb,
+        get_cell_size,
+        get_scale_aspect,
+        get_terminal_background,
+        get_view_size,
+        get_winsize_cell_size,
+        is_float_image,
+        parse_color,
+        parse_colors,
+        par_rows,
+        resize_linear,
+        resize_parallel,
+        rgba_to_rgb,
+        rgba_to_rgb_linear,
+        scroll_matches,
+        scroll_model,
+        scroll_region,
+        step_window,
+        to_display_rgba8,
+        Adjustment,
+        Adjustments,
+        AsciiArt,
+        BandwidthStats,
+        Background,
+        Budget,
+        Checker,
+        ChannelView,
+        CheckerUnit,
+        Clahe,
+        Colormap,
+        EnhanceMode,
         Float,
         FmtColor,
+        FrameReader,
+        Histogram,
+        ImageLoader,
+        ImageSource,
+        Levels,
+        Memo,
+        NpyRange,
+        Orientation,
         Position,
+        Pyramid,
+        RawLayout,
+        Rgba,
         SizeType,
+        Slideshow,
+        TermInput,
+        ToneMapping,
+        CLAHE_CLIP_LIMIT,
+        CLAHE_TILES,
+        DEFAULT_HIST_BACKGROUND,
+        DEFAULT_MASK_COLOR,
+        DEFAULT_RAMP,
+        DEFAULT_SLIDESHOW_INTERVAL,
         ESC
     },
 };
 
 
+/// 流模式中等待终端输入时, 检查新帧的间隔
+const STREAM_POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// 检查监视的文件或目录是否改变的间隔
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+/// 快速预览时使用的最小 opt 等级
+const PREVIEW_OPT_LEVEL: u32 = 150;
+/// 合并连续的输入时, 最多推迟渲染的时长
+const MAX_RENDER_DELAY: Duration = Duration::from_millis(200);
+/// 像素检查器标记的默认颜色
+const DEFAULT_INSPECTOR_COLOR: Rgb = [255, 0, 255];
+
 const FILTERS: &[FilterType] = &[
     FilterType::Nearest, FilterType::Triangle,
     FilterType::CatmullRom, FilterType::Gaussian,
     FilterType::Lanczos3
 ];
 
-pub type Rgba = [u8; 4];
+/// 缓存的帧所对应的视区与缩放参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FrameKey {
+    win_pos: Position,
+    view_size: Position,
+    term_size: Position,
+    scale: Float,
+    pixel_aspect: Float,
+    filter_idx: usize,
+    linear: bool,
+    channel_view: ChannelView,
+}
 
-/// RGBA color to RGB color
-/// # Examples
-/// ```
-/// use timg::rgba_to_rgb;
-/// assert_eq!(rgba_to_rgb([100, 149, 237, 200], [255; 3]), [164, 195, 240]);
-/// ```
-pub fn rgba_to_rgb(foreground: Rgba, background: Rgb) -> Rgb {
-    macro_rules! int {
-        ( $x:expr ) => {
-            ($x) as u8
+/// 处理后的帧所对应的参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ProcessKey {
+    frame: FrameKey,
+    tone_mapping: ToneMapping,
+    enhance: EnhanceMode,
+    adjustments: Adjustments,
+    invert: bool,
+    grayscale: bool,
+    colormap: Option<Colormap>,
+    colormap_window: [u8; 2],
+}
+
+/// 裁剪出视区, 并缩放至刚好放进终端大小
+/// 虚拟像素并非正方形时纵向与横向的缩放比例不同
+///
+/// 通道及透明度遮罩的颜色在缩放前从未预乘透明度的源像素中选择,
+/// 透明的部分也保留各通道的值
+fn get_frame(img: &DynamicImage,
+             win_pos: Position,
+             view_size: Position,
+             term_size: Position,
+             filter: FilterType,
+             linear: bool,
+             (channel_view, mask_color): (ChannelView, Rgb)) -> DynamicImage {
+    let mut img = img.crop_imm(win_pos.x, win_pos.y, view_size.x, view_size.y);
+    if channel_view != ChannelView::All {
+        img = match img {
+            DynamicImage::ImageLuma8(_) | DynamicImage::ImageLumaA8(_)
+                | DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => {
+                let mut rgba = img.into_rgba8();
+                par_rows(&mut rgba, available_threads(), |_, band| {
+                    channel_view.apply(band, mask_color)
+                });
+                DynamicImage::ImageRgba8(rgba)
+            },
+            img => {
+                let mut rgba = img.into_rgba32f();
+                par_rows(&mut rgba, available_threads(), |_, band| {
+                    channel_view.apply(band, mask_color)
+                });
+                DynamicImage::ImageRgba32F(rgba)
+            },
         };
     }
-    macro_rules! float {
-        ( $x:expr ) => {
-            ($x) as Float
-        };
+    let fit = |len: SizeType, view_len: SizeType, term_len: SizeType| {
+        ((len as Float * term_len as Float / view_len.max(1) as Float)
+         .round() as SizeType).clamp(1, term_len)
+    };
+    let width = fit(img.width(), view_size.x, term_size.x);
+    let height = fit(img.height(), view_size.y, term_size.y);
+    if linear {
+        resize_linear(&img, width, height, filter)
+    } else {
+        resize_parallel(&img, width, height, filter, available_threads())
+    }
+}
+
+
+/// 在另一个线程中读取流的帧, 读取出错或结束后停止
+fn spawn_frame_reader<R>(mut reader: FrameReader<R>)
+    -> Receiver<io::Result<DynamicImage>>
+    where R: Read + Send + 'static
+{
+    // 容量较小, 以免显示较慢时堆积过多的帧
+    let (sender, receiver) = mpsc::sync_channel(1);
+    thread::spawn(move || {
+        while let Some(frame) = reader.next_frame().transpose() {
+            let is_err = frame.is_err();
+            if sender.send(frame).is_err() || is_err {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// 等待时发生的事件
+enum Event {
+    /// 终端有输入可读
+    Input,
+    /// 图片的来源已改变, 需要重新读取
+    SourceChanged,
+    /// 到了幻灯片切换的时间
+    Advance,
+    /// 流中的最新一帧
+    Frame(DynamicImage),
+    /// 流已结束, 或出现了错误
+    StreamEnd(Option<io::Error>),
+}
+
+/// 等待终端的输入, 同时接收流中的新帧与检查图片来源的改变,
+/// 或者等到 `deadline` 时切换幻灯片, 终端的输入优先
+fn wait_event<R: Read>(tty_input: &TermInput<R>,
+                       frames: Option<&Receiver<io::Result<DynamicImage>>>,
+                       mut source: Option<&mut ImageSource>,
+                       deadline: Option<Instant>) -> Event {
+    let watching = source.as_ref()
+        .is_some_and(|source| source.is_watching());
+    let interval = match (frames, watching) {
+        (Some(_), _) => Some(STREAM_POLL_INTERVAL),
+        (None, true) => Some(WATCH_INTERVAL),
+        (None, false) => None,
+    };
+    if interval.is_none() && deadline.is_none() {
+        return Event::Input;
+    }
+    loop {
+        if tty_input.poll(Some(Duration::ZERO)) {
+            return Event::Input;
+        }
+        let now = Instant::now();
+        if deadline.is_some_and(|deadline| now >= deadline) {
+            return Event::Advance;
+        }
+        if watching && source.as_mut().is_some_and(|source| source.changed()) {
+            return Event::SourceChanged;
+        }
+        // 仅保留最新的一帧
+        let mut latest = None;
+        while let Some(frames) = frames {
+            match frames.try_recv() {
+                Ok(Ok(frame)) => latest = Some(frame),
+                Ok(Err(e)) => return Event::StreamEnd(Some(e)),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => match latest {
+                    Some(_) => break,
+                    None => return Event::StreamEnd(None),
+                },
+            }
+        }
+        if let Some(frame) = latest {
+            return Event::Frame(frame);
+        }
+        let timeout = deadline
+            .map(|deadline| deadline - now)
+            .into_iter().chain(interval)
+            .min();
+        if tty_input.poll(timeout) {
+            return Event::Input;
+        }
     }
-    let [r1, g1, b1, a1] = foreground;
-    let [r2, g2, b2] = background;
-    let alpha = a1 as Float / 255.0;
-    let [r, g, b]: [u8; 3];
-    r = int!(float!(r1) * alpha + float!(r2) * (1.0 - alpha));
-    g = int!(float!(g1) * alpha + float!(g2) * (1.0 - alpha));
-    b = int!(float!(b1) * alpha + float!(b2) * (1.0 - alpha));
-    [r, g, b]
 }
 
 
@@ -111,20 +331,72 @@ pub fn run(matches: ArgMatches) {
         };
     }
 
-    let rgb_back_grounds: Vec<Rgb> = get_value!("bgs", "000000,888888,ffffff")
-        .split(',').map(|s| {
-            if s.len() != 6 {
-                log!(e:(3) "StrLenError: {:?} length is {}, need 6.", s, s.len())
-            }
-            num_to_rgb(base16_to_unum(s).unwrap_or_else(|| {
-                log!(e:(3) "StrToHexError: {:?} is not a base16 string.", s)
-            }))
-        }).collect::<Vec<_>>();
-    let mut back_grounds: Vec<Color> = Vec::with_capacity(rgb_back_grounds.len() + 1);
-    back_grounds.push(Color::None);
-    for i in rgb_back_grounds {
-        back_grounds.push(Color::Rgb(i))
+    let parse_colors = |colors: &str| -> Vec<Color> {
+        parse_colors(colors).unwrap_or_else(|e| {
+            log!(e:(3) "ParseColorError: {}", e)
+        })
+    };
+    // 单个不可以为 none 的颜色
+    let parse_rgb = |color: &str, name: &str| -> Rgb {
+        let color = parse_color(color).unwrap_or_else(|e| {
+            log!(e:(3) "ParseColorError: {}", e)
+        });
+        color_to_rgb(color).unwrap_or_else(|| {
+            log!(e:(3) "ParseColorError: {} can't be none", name)
+        })
+    };
+    let mask_color: Rgb = get_value!("mask_color")
+        .map_or(DEFAULT_MASK_COLOR, |color| parse_rgb(color, "mask color"));
+    let hist_back_ground: Rgb = get_value!("hist_bg")
+        .map_or(DEFAULT_HIST_BACKGROUND, |color| {
+            parse_rgb(color, "histogram background")
+        });
+    let inspector_color: Rgb = get_value!("inspector_color")
+        .map_or(DEFAULT_INSPECTOR_COLOR, |color| {
+            parse_rgb(color, "inspector color")
+        });
+    let color_back_grounds: Vec<Color>
+        = parse_colors(get_value!("bgs", "000000,888888,ffffff"));
+    let checker: Checker = {
+        let colors = parse_colors(get_value!("checker_colors", "666666,999999"))
+            .into_iter().map(|color| color_to_rgb(color).unwrap_or_else(|| {
+                log!(e:(3) "ParseColorError: checker color can't be none")
+            })).collect::<Vec<_>>();
+        if colors.len() != 2 {
+            log!(e:(3) "need length is 2, found {}", colors.len())
+        }
+        let s = get_value!("checker_size", "4");
+        let size: SizeType = s.parse().unwrap_or_else(
+            |e| log!(e:(3) "StrToIntError: {}", e));
+        if size == 0 {
+            log!(e:(3) "NumberOutOfRange: {} not in (0,inf)", size)
+        }
+        let unit = if matches.is_present("checker_source") {
+            CheckerUnit::Source
+        } else {
+            CheckerUnit::Screen
+        };
+        Checker::new([colors[0], colors[1]], size, unit)
+    };
+    let set_term_back_ground: Rgb = {
+        let color = parse_colors(get_value!("term_bg", "000000"));
+        if color.len() != 1 {
+            log!(e:(3) "need length is 1, found {}", color.len())
+        }
+        color_to_rgb(color[0]).unwrap_or_else(|| {
+            log!(e:(3) "ParseColorError: terminal background can't be none")
+        })
+    };
+    let mut back_grounds: Vec<Background>
+        = Vec::with_capacity(color_back_grounds.len() + 2);
+    if ! color_back_grounds.contains(&Color::None) {
+        // 未指定时, 终端背景色总是排在第一个
+        back_grounds.push(Color::None.into());
     }
+    for i in color_back_grounds {
+        back_grounds.push(i.into())
+    }
+    back_grounds.push(Background::Checker(checker));
     let zoom_sub_ratio = {
         let s = get_value!("zoom_ratio", "0.8");
         let num: Float = s.parse().unwrap_or_else(
@@ -178,6 +450,36 @@ pub fn run(matches: ArgMatches) {
             None
         }
     };
+    let set_cell_aspect: Option<Float> = get_value!("cell_aspect").map(|s| {
+        let num: Float = s.parse().unwrap_or_else(
+            |e| log!(e:(3) "StrToFloatError: {}", e));
+        if num <= 0.0 {
+            log!(e:(3) "NumberOutOfRange: {} not in (0,inf)", num)
+        }
+        num
+    });
+    // 在线性光下混合与缩放
+    let mut linear_light: bool = ! matches.is_present("no_linear");
+    let mut ascii_art = AsciiArt::new(get_value!("ramp", DEFAULT_RAMP));
+    ascii_art.edges = matches.is_present("edges");
+    let mut ascii_mode: bool = matches.is_present("ascii");
+    /// 获取终端的大小, 按像素算
+    macro_rules! get_term_size {
+        () => {
+            Position::from(if let Some(size) = set_term_size {
+                [size.x, size.y * 2]
+            } else {
+                match dimensions() {
+                    Some(x) => [x.0 as SizeType, x.1 as SizeType * 2],
+                    None => {
+                        log!(e "GetTerminalSizeError. use default: {:?}",
+                             DEFAULT_TERM_SIZE);
+                        DEFAULT_TERM_SIZE
+                    },
+                }
+            })
+        };
+    }
 
 
     macro_rules! clear_screen {
@@ -185,41 +487,200 @@ pub fn run(matches: ArgMatches) {
             eprint!("\x1b[2J"); // 清空屏幕
         };
     }
-    let path = matches
-        .value_of_os("FILE")
-        .unwrap_or_else(|| {
+    let paths: Vec<PathBuf> = matches.values_of_os("FILE")
+        .map(|paths| paths.map(PathBuf::from).collect())
+        .unwrap_or_default();
+    let path = paths.first();
+    let raw_layout: Option<RawLayout> = matches.value_of("raw")
+        .map(|layout| layout.parse().unwrap_or_else(|e| {
+            log!(e:(3) "ParseRawError: {}", e);
+        }));
+    let npy_range: NpyRange = matches.value_of("npy_range")
+        .map(|range| range.parse().unwrap_or_else(|e| {
+            log!(e:(3) "ParseNpyRangeError: {}", e);
+        }))
+        .unwrap_or(NpyRange::Auto);
+    let loader: ImageLoader = ImageLoader { raw_layout, npy_range };
+    // 非流模式中图片的来源, 用于重新读取
+    let mut source: Option<ImageSource> = None;
+    // 流模式中后续的帧, 在另一个线程中读取
+    let mut frames: Option<Receiver<io::Result<DynamicImage>>> = None;
+    let mut repr_img: DynamicImage = if matches.is_present("stream") {
+        let input: Box<dyn Read + Send> = match path {
+            Some(path) if path != "-" => {
+                Box::new(File::open(path).unwrap_or_else(|e| {
+                    log!(e:(2) "OpenStreamError: {}", e);
+                }))
+            },
+            _ => Box::new(io::stdin()),
+        };
+        let mut reader = FrameReader::new(input).unwrap_or_else(|e| {
+            log!(e:(2) "ReadStreamError: {}", e);
+        });
+        let first = match reader.next_frame() {
+            Ok(Some(frame)) => frame,
+            Ok(None) => log!(e:(2) "ReadStreamError: empty stream"),
+            Err(e) => log!(e:(2) "ReadStreamError: {}", e),
+        };
+        frames = Some(spawn_frame_reader(reader));
+        first
+    } else {
+        let path = path.unwrap_or_else(|| {
             log!(e:(1) "GetFileError. use `-H` option print help");
         });
-    let mut repr_img
-        = image::open(path).unwrap_or_else(|e| {
-            log!(e:(2) "ReadImageError: {}", e);
+        let watch = matches.is_present("watch");
+        let source = source.insert(if paths.len() > 1 {
+            ImageSource::files(paths.clone(), loader, watch)
+        } else if path.is_dir() {
+            ImageSource::dir(path, loader)
+        } else {
+            ImageSource::file(path, loader, watch)
         });
-    let img_size = Position::from([repr_img.width(), repr_img.height()]);
-    let mut stdin = stdin().into_raw_mode().unwrap_or_else(|e| {
+        match source.load() {
+            Some(img) => img.unwrap_or_else(|e| {
+                log!(e:(2) "ReadImageError: {}", e);
+            }),
+            None if matches.is_present("dump") => {
+                log!(e:(2) "ReadImageError: no image in {}", path.display());
+            },
+            // 目录中还没有图片, 先显示空白, 第一个图片由监视的轮询读取
+            None => DynamicImage::new_rgba8(1, 1),
+        }
+    };
+    let mut img_size = Position::from([repr_img.width(), repr_img.height()]);
+    // 多个文件时的幻灯片, 给出间隔时立即开始播放
+    let mut slideshow: Option<Slideshow> = source.as_ref()
+        .filter(|source| source.position().is_some_and(|(len, _)| len > 1))
+        .map(|_| {
+            let interval = get_value!("slideshow").map(|s| {
+                let secs: Float = s.parse().unwrap_or_else(
+                    |e| log!(e:(3) "StrToFloatError: {}", e));
+                if secs <= 0.0 || ! secs.is_finite() {
+                    log!(e:(3) "NumberOutOfRange: {} not in (0,inf)", secs)
+                }
+                Duration::from_secs_f64(secs)
+            });
+            let mut slideshow = Slideshow::new(
+                interval.unwrap_or(DEFAULT_SLIDESHOW_INTERVAL));
+            if interval.is_some() {
+                slideshow.play();
+            }
+            slideshow
+        });
+    if matches.is_present("dump") {
+        // 非交互模式, 将整个图片以字符画输出后退出
+        let term_size: Position = get_term_size!();
+        let pixel_aspect: Float = set_cell_aspect.map(|x| x / 2.0)
+            .or_else(|| get_winsize_cell_size(libc::STDOUT_FILENO)
+                     .map(cell_pixel_aspect))
+            .unwrap_or(1.0);
+        let scale: Float = get_scale_aspect(term_size, img_size, pixel_aspect);
+        let img = get_frame(&repr_img,
+                            Position::default(),
+                            get_view_size(term_size, scale, pixel_aspect),
+                            term_size,
+                            FilterType::Lanczos3,
+                            linear_light,
+                            (ChannelView::All, mask_color));
+        let img = DynamicImage::ImageRgba8(to_display_rgba8(&img));
+        for line in ascii_art.render(&img.into_luma8()) {
+            println!("{}", line.trim_end());
+        }
+        return;
+    }
+    // 直接读取终端, 而不是带缓冲的 stdin, 以便能够等待终端的回复
+    let tty = OpenOptions::new().read(true).write(true).open("/dev/tty")
+        .unwrap_or_else(|e| {
+            log!(e:(2) "OpenTtyError: {}", e);
+        });
+    let tty_fd: RawFd = tty.as_raw_fd();
+    let mut tty_input = TermInput::new(tty.into_raw_mode().unwrap_or_else(|e| {
         log!(e:(2) "GetStdInError: {}", e);
-    });
-    let is_alpha: bool = repr_img.color().has_alpha();
+    }), tty_fd);
+    let mut is_alpha: bool = repr_img.color().has_alpha();
+    let mut orientation: Orientation = Orientation::new();
+    let mut enhance: EnhanceMode = EnhanceMode::Off;
+    // 由整个图片计算的增强参数, 仅在首次使用时计算
+    let mut levels: Option<Levels> = None;
+    let mut clahe: Option<Clahe> = None;
+    let [mut show_hist, mut hist_viewport] = [false; 2];
+    let mut whole_hist: Option<Histogram> = None;
+    // 渲染各阶段的缓存, 图片本身改变时需要清除
+    let mut frame_cache: Memo<FrameKey, DynamicImage> = Memo::new();
+    let mut processed: Memo<ProcessKey, RgbaImage> = Memo::new();
+    let mut view_hist: Memo<[Position; 2], Histogram> = Memo::new();
+    let mut pyramid: Pyramid = Pyramid::new();
+    // 整个图片的 8 位 RGBA, 由增强与直方图共用, 仅转换一次
+    let mut rgba_img: Option<RgbaImage> = None;
+    macro_rules! clear_caches {
+        () => {
+            frame_cache.clear();
+            processed.clear();
+            view_hist.clear();
+            pyramid.clear();
+            rgba_img = None;
+        };
+    }
+    macro_rules! rgba_img {
+        () => {
+            rgba_img.get_or_insert_with(|| to_display_rgba8(&repr_img))
+        };
+    }
+    /// 替换当前的图片, 保留翻转与旋转, 并清除由图片计算的缓存
+    macro_rules! set_image {
+        ( $img:expr ) => {{
+            repr_img = orientation.apply(&$img);
+            img_size = Position::from([repr_img.width(), repr_img.height()]);
+            is_alpha = repr_img.color().has_alpha();
+            levels = None;
+            clahe = None;
+            whole_hist = None;
+            clear_caches!();
+        }};
+    }
+    // 上一次输出帧的时间
+    let mut last_render: Instant = Instant::now();
+    /// 终端有尚未读取的输入时跳过渲染, 但持续按键时也不会推迟太久
+    macro_rules! skip_render {
+        () => {
+            last_render.elapsed() < MAX_RENDER_DELAY
+                && tty_input.poll(Some(Duration::ZERO))
+        };
+    }
+    let progressive: bool = ! matches.is_present("no_progressive");
+    let mut refine: bool = false; // 下一次渲染是预览之后的完整质量
+    // 下一次渲染的图片来自流, 监视或幻灯片, 而非按键, 直接渲染完整质量
+    let mut from_source: bool = frames.is_some();
+    let mut show_stats: bool = matches.is_present("stats");
+    let budget: Option<Budget> = matches.value_of("adaptive")
+        .map(|budget| budget.parse().unwrap_or_else(|e| {
+            log!(e:(3) "ParseBudgetError: {}", e);
+        }));
+    let mut adaptive: bool = budget.is_some(); // 自动调整 opt 以接近预算
+    let mut stats: BandwidthStats = BandwidthStats::new();
+    let mut preview_usage: (usize, Duration) = (0, Duration::ZERO); // 预览帧的用量
     let mut is_start: bool = true;
     let mut readbuf: [u8; 1] = [0];
+    // 是否需要查询终端, 每次查询都可能需要等待, 仅在启动与 `R` 时查询
+    let mut query_term: bool = true;
+    let mut pixel_aspect: Float = 1.0; // 虚拟像素的高宽比
+    let mut term_back_ground: Rgb = set_term_back_ground; // 终端的背景色, 用于混合透明的像素
     'main: loop { // 部分参数初始化将在这个头部进行
         let mut term_size: Position /* 终端的大小, 按像素算 */
-            = Position::from(if let Some(size) = set_term_size {
-                [size.x, size.y * 2]
-            } else {
-                match dimensions() {
-                    Some(x) => [x.0 as SizeType, x.1 as SizeType * 2],
-                    None => {
-                        log!(e "GetTerminalSizeError. use default: {:?}",
-                             DEFAULT_TERM_SIZE);
-                        DEFAULT_TERM_SIZE
-                    },
-                }
-            });
+            = get_term_size!();
         if is_start {
             eprint!("\x1b[{}S", term_size.y >> 1); // 滚动一个屏幕, 以空出空间
         }
         term_size.y -= 2; // 缩小终端大小一文本行以留给状态行
-        let full_scale: Float = get_scale(term_size, img_size);
+        if mem::take(&mut query_term) {
+            pixel_aspect = set_cell_aspect.map(|x| x / 2.0)
+                .or_else(|| get_cell_size(&mut tty_input).map(cell_pixel_aspect))
+                .unwrap_or(1.0);
+            term_back_ground = get_terminal_background(&mut tty_input)
+                .unwrap_or(set_term_back_ground);
+        }
+        let mut full_scale: Float
+            = get_scale_aspect(term_size, img_size, pixel_aspect);
         clear_screen!();
         new_and_init_macro!{
             // scale alias ratio.
@@ -232,99 +693,393 @@ pub fn run(matches: ArgMatches) {
         screen_buf.cfg.chromatic_aberration = default_opt_level;
         let mut filter_idx = 4;
         let [mut grayscale, mut invert] = [false; 2];
+        let mut channel_view: ChannelView = ChannelView::All;
+        let mut colormap: Option<Colormap> = None;
+        let mut colormap_window: [u8; 2] = [0, 255]; // 映射到色图两端的值
+        let mut adjustments: Adjustments = Adjustments::new();
+        let mut adjustment: Adjustment = Adjustment::Brightness; // 当前选中的调整项
+        let mut tone_mapping: ToneMapping = ToneMapping::new();
+        let mut show_inspector: bool = false;
         let mut error_buf: String = String::new();
+        let mut ascii_lines: Vec<String> = Vec::new(); // 上一次输出的字符画
+        let mut last_view: Option<(Position, Float)> = None; // 上一次输出的位置与缩放
+        macro_rules! redraw {
+            () => {
+                clear_screen!();
+                screen_buf.init_bg_colors();
+                ascii_lines.clear();
+                last_view = None;
+            };
+        }
+        /// 图片大小改变后重新适应终端, 保留其它的显示设置
+        macro_rules! refit {
+            () => {
+                full_scale = get_scale_aspect(term_size, img_size, pixel_aspect);
+                init_scale!();
+                init_win_pos!();
+                last_view = None;
+            };
+        }
         loop {
             screen_buf.cfg.default_color
-                = back_grounds[back_ground_color_idx];
-            let scale_term_size = term_size.mul_scale(scale);
-            let mut img
-                = repr_img.crop_imm(win_pos.x,
-                               win_pos.y,
-                               scale_term_size.x,
-                               scale_term_size.y)
-                .resize(
-                    term_size.x,
-                    term_size.y,
-                    FILTERS[filter_idx]);
-            if invert {
-                img.invert()
-            }
-            if grayscale {
-                img = img.grayscale()
+                = back_grounds[back_ground_color_idx].fill_color();
+            let scale_term_size
+                = get_view_size(term_size, scale, pixel_aspect);
+            let mut frame_key: FrameKey = FrameKey {
+                win_pos,
+                view_size: scale_term_size,
+                term_size,
+                scale,
+                pixel_aspect,
+                filter_idx,
+                linear: linear_light,
+                channel_view,
+            };
+            // 视区改变时先以最近邻与较高的 opt 快速预览, 没有新的输入时再渲染选定的质量
+            // 两个标记都只作用于这一次渲染
+            let is_refine: bool = mem::take(&mut refine);
+            let is_from_source: bool = mem::take(&mut from_source);
+            let preview: bool = progressive
+                && ! is_refine
+                && ! is_from_source
+                && filter_idx != 0
+                && ! frame_cache.is_cached(&frame_key);
+            if preview {
+                frame_key.filter_idx = 0;
             }
-            { /* flush to screen buffer */
-                screen_buf.init_colors();
-                let mut count: usize = 0;
-                let img_width: usize = img.width() as usize;
-                let line_add_idx: usize = term_size.x as usize - img_width;
-                let mut i: usize = 0;
-                macro_rules! flush {
-                    ( $i:ident in $from:expr => $f:expr ) => {
-                        for $i in $from {
-                            screen_buf.set_idx(i, Color::Rgb($f));
-                            i += 1;
-                            count += 1;
-                            if count == img_width {
-                                i += line_add_idx;
-                                count = 0;
-                            }
-                        }
+            let mut preview_shown: bool = false;
+            // 还有未处理的输入时跳过渲染, 合并连续的按键后只渲染一次
+            'render: {
+                let render_start: Instant = Instant::now();
+                if skip_render!() {
+                    break 'render;
+                }
+                let frame: &DynamicImage = frame_cache.get_or_insert_with(
+                    frame_key,
+                    || {
+                        // 从分辨率仍不低于输出的最小层级重新采样
+                        let level = Pyramid::level_for_scale(
+                            scale.min(scale * pixel_aspect));
+                        let level_img = pyramid.level(&repr_img, level, linear_light);
+                        let [fx, fy] = [
+                            level_img.width() as Float / img_size.x as Float,
+                            level_img.height() as Float / img_size.y as Float,
+                        ];
+                        let level_pos = Position::new(
+                            (win_pos.x as Float * fx) as SizeType,
+                            (win_pos.y as Float * fy) as SizeType);
+                        let level_view_size = Position::new(
+                            ((scale_term_size.x as Float * fx).ceil() as SizeType).max(1),
+                            ((scale_term_size.y as Float * fy).ceil() as SizeType).max(1));
+                        get_frame(level_img,
+                                  level_pos,
+                                  level_view_size,
+                                  term_size,
+                                  FILTERS[frame_key.filter_idx],
+                                  linear_light,
+                                  (channel_view, mask_color))
+                    });
+                if skip_render!() {
+                    break 'render; // 缩放后有了新的输入, 跳过这次渲染余下的阶段
+                }
+                // 帧中的像素中心对应到源图片的位置
+                let to_source = |x: SizeType, y: SizeType| [
+                    win_pos.x as Float + (x as Float + 0.5) * scale,
+                    win_pos.y as Float + (y as Float + 0.5) * scale * pixel_aspect,
+                ];
+                let process_key: ProcessKey = ProcessKey {
+                    frame: frame_key,
+                    tone_mapping,
+                    enhance,
+                    adjustments,
+                    invert,
+                    grayscale,
+                    colormap,
+                    colormap_window,
+                };
+                // 未使用缓存的帧, 自适应时以此衡量输出的用量
+                let fresh: bool = ! processed.is_cached(&process_key);
+                // 处理后的帧, 仅在参数改变时重新处理, 并且只转换一次为 RGBA
+                let img: &RgbaImage = processed.get_or_insert_with(process_key, || {
+                    let mut img = if tone_mapping.is_identity() && ! is_float_image(frame) {
+                        frame.clone()
+                    } else {
+                        // 在转换为 8 位之前, 以完整的精度处理, 浮点图片总是需要编码为 sRGB
+                        DynamicImage::ImageRgba8(tone_mapping.apply(frame))
                     };
+                    if enhance != EnhanceMode::Off || ! adjustments.is_identity() {
+                        let mut rgba = img.into_rgba8();
+                        // 映射表在各线程间共享, 仅计算一次
+                        let levels: Option<&Levels> = (enhance == EnhanceMode::Levels)
+                            .then(|| &*levels.get_or_insert_with(
+                                    || Levels::new(rgba_img!())));
+                        let clahe: Option<&Clahe> = (enhance == EnhanceMode::Clahe)
+                            .then(|| &*clahe.get_or_insert_with(
+                                    || Clahe::new(rgba_img!(),
+                                                  CLAHE_TILES,
+                                                  CLAHE_CLIP_LIMIT)));
+                        par_rows(&mut rgba, available_threads(), |first, band| {
+                            if let Some(levels) = levels {
+                                levels.apply(band);
+                            }
+                            if let Some(clahe) = clahe {
+                                clahe.apply(band, |x, y| to_source(x, first + y));
+                            }
+                            adjustments.apply(band);
+                        });
+                        img = DynamicImage::ImageRgba8(rgba);
+                    }
+                    if invert {
+                        img.invert()
+                    }
+                    if grayscale {
+                        img = img.grayscale()
+                    }
+                    let mut rgba = img.into_rgba8();
+                    if let Some(colormap) = colormap {
+                        // 映射亮度, 或经过 channel_view 选择的通道
+                        par_rows(&mut rgba, available_threads(), |_, band| {
+                            colormap.apply(band, colormap_window)
+                        });
+                    }
+                    rgba
+                });
+                if skip_render!() {
+                    break 'render;
                 }
-                let rgb
-                    = if let Color::Rgb(x)
-                    = back_grounds[back_ground_color_idx] {
-                    Some(x)
+                // 检查器所在的帧中心, 及对应的源图片像素
+                let inspect_pos: Position = Position::new(img.width() >> 1,
+                                                          img.height() >> 1);
+                let inspect_status: String = if show_inspector {
+                    let [x, y] = to_source(inspect_pos.x, inspect_pos.y);
+                    // 以所读取的图片本身的大小限制, 旋转后也不会越界
+                    let [x, y] = [(x as SizeType).min(repr_img.width() - 1),
+                                  (y as SizeType).min(repr_img.height() - 1)];
+                    format!("Px[{},{}]{} ", x, y, fmt_pixel(&repr_img, x, y))
                 } else {
+                    String::new()
+                };
+                // 源图片的直方图, 整个图片的仅计算一次
+                if show_hist && whole_hist.is_none() {
+                    whole_hist = Some(Histogram::new(rgba_img!().pixels()));
+                }
+                let hist: Option<&Histogram> = if ! show_hist {
                     None
+                } else if hist_viewport {
+                    Some(view_hist.get_or_insert_with(
+                            [win_pos, scale_term_size],
+                            || Histogram::new(rgba_img!().rows()
+                                .skip(win_pos.y as usize)
+                                .take(scale_term_size.y as usize)
+                                .flat_map(|row| row
+                                          .skip(win_pos.x as usize)
+                                          .take(scale_term_size.x as usize)))))
+                } else {
+                    whole_hist.as_ref()
                 };
-                match rgb {
-                    Some(rgb) if is_alpha => {
-                        flush!(color in img.into_rgba8().pixels()
-                               => rgba_to_rgb(
-                                   color.0,
-                                   rgb));
+                let mut frame_output: String = String::new();
+                if ascii_mode { /* 仅输出变化了的字符画行 */
+                    let lines = ascii_art.render(&imageops::grayscale(img));
+                    for (i, line) in lines.iter().enumerate() {
+                        if ascii_lines.get(i) != Some(line) {
+                            frame_output.push_str(
+                                &format!("\x1b[{}H{}\x1b[K", i + 1, line));
+                        }
+                    }
+                    for i in lines.len()..ascii_lines.len() {
+                        frame_output.push_str(&format!("\x1b[{}H\x1b[K", i + 1));
+                    }
+                    frame_output.push_str(
+                        &format!("\x1b[{}H", (term_size.y >> 1) + 1));
+                    ascii_lines = lines;
+                    last_view = None;
+                } else { /* flush to screen buffer */
+                    screen_buf.init_colors();
+                    let mut count: usize = 0;
+                    let img_width: usize = img.width() as usize;
+                    let line_add_idx: usize = term_size.x as usize - img_width;
+                    let mut i: usize = 0;
+                    macro_rules! flush {
+                        ( $i:pat in $from:expr => $f:expr ) => {
+                            for $i in $from {
+                                screen_buf.set_idx(i, Color::Rgb($f));
+                                i += 1;
+                                count += 1;
+                                if count == img_width {
+                                    i += line_add_idx;
+                                    count = 0;
+                                }
+                            }
+                        };
+                    }
+                    let blend: fn(Rgba, Rgb) -> Rgb = if linear_light {
+                        rgba_to_rgb_linear
+                    } else {
+                        rgba_to_rgb
+                    };
+                    match back_grounds[back_ground_color_idx] {
+                        Background::Color(back_ground) if is_alpha => {
+                            let rgb = color_to_rgb(back_ground)
+                                .unwrap_or(term_back_ground);
+                            let rgbs = composite(img, available_threads(), blend,
+                                                 |_, _| rgb);
+                            flush!(rgb in rgbs => rgb);
+                        }
+                        Background::Checker(checker) if is_alpha => {
+                            let rgbs = composite(img, available_threads(), blend,
+                                                 |x, y| match checker.unit {
+                                CheckerUnit::Screen => checker.color_at(x, y),
+                                CheckerUnit::Source => {
+                                    let [x, y] = to_source(x, y);
+                                    checker.color_at(x as SizeType, y as SizeType)
+                                },
+                            });
+                            flush!(rgb in rgbs => rgb);
+                        }
+                        _ => {
+                            flush!(color in img.pixels()
+                                   => [color[0], color[1], color[2]]);
+                        }
                     }
-                    _ => {
-                        flush!(color in img.into_rgb8().pixels() => color.0);
+                    if let Some(hist) = hist {
+                        // 在右下角覆盖直方图面板
+                        let [width, height] = [term_size.x.min(128),
+                                               (term_size.y / 3).min(32) & !1];
+                        let panel = hist.draw(width, height, hist_back_ground);
+                        let [x0, y0] = [term_size.x - width, term_size.y - height];
+                        for (x, y, color) in panel.enumerate_pixels() {
+                            screen_buf.set_idx(
+                                ((y0 + y) * term_size.x + x0 + x) as usize,
+                                Color::Rgb(color.0));
+                        }
                     }
+                    if show_inspector {
+                        // 在中心的四周标记, 不遮挡中心的像素
+                        let Position { x, y } = inspect_pos;
+                        for [x, y] in [[x.wrapping_sub(1), y], [x + 1, y],
+                                       [x, y.wrapping_sub(1)], [x, y + 1]] {
+                            if x < term_size.x && y < term_size.y {
+                                screen_buf.set_idx((y * term_size.x + x) as usize,
+                                                   Color::Rgb(inspector_color));
+                            }
+                        }
+                    }
+                    // 纵向平移时先滚动终端中已有的内容, 仅需重绘露出的行
+                    let scroll_rows: i32 = match last_view {
+                        Some((pos, last_scale))
+                            if last_scale == scale
+                            && pos.x == win_pos.x && pos.y != win_pos.y =>
+                        {
+                            let dy = win_pos.y as Float - pos.y as Float;
+                            (dy / (scale * pixel_aspect) / 2.0).round() as i32
+                        },
+                        _ => 0,
+                    };
+                    let text_rows = (term_size.y >> 1) as i32;
+                    if scroll_rows != 0 && scroll_rows.abs() < text_rows
+                        && scroll_matches(&screen_buf, scroll_rows)
+                            > scroll_matches(&screen_buf, 0)
+                                + (term_size.x * term_size.y) as usize / 4
+                    {
+                        scroll_model(&mut screen_buf, scroll_rows);
+                        frame_output = scroll_region(scroll_rows, text_rows as u32);
+                    }
+                    let opt = screen_buf.cfg.chromatic_aberration;
+                    if preview {
+                        screen_buf.cfg.chromatic_aberration = opt.max(PREVIEW_OPT_LEVEL);
+                    }
+                    frame_output += &format!("\x1b[H{}", screen_buf.flush(false));
+                    screen_buf.cfg.chromatic_aberration = opt;
+                    last_view = Some((win_pos, scale));
                 }
-            }
-            let status_line: String = format!(concat!(
-                    "\x1b[7m",
-                    "ImgSize[{}x{}] ",
-                    "Pos[{},{}] ",
-                    "Ratio[{:.2}] ",
-                    "Opt[{}] ",
-                    "Fl[{}] ",
-                    "Help(H) ",
-                    "Quit(Q)",
-                    "\x1b[0m\x1b[s{}\x1b[K\x1b[u"),
-                    img_size.x, img_size.y,
-                    win_pos.x, win_pos.y,
-                    scale,
-                    screen_buf.cfg.chromatic_aberration,
-                    filter_idx,
-                    error_buf);
-            error_buf.clear();
-            eprint!("\x1b[H{}{}", screen_buf.flush(false), status_line);
-            is_start = false;
-            macro_rules! read_char {
-                () => {
-                    stdin.read_exact(&mut readbuf).unwrap_or_else(|e| {
-                        log!(e:(2) "ReadCharError: {}", e)
-                    })
+                let adjust_status: String = if adjustments.is_identity() {
+                    String::new()
+                } else {
+                    let default = Adjustments::default();
+                    format!("Adj[{}] ", Adjustment::ALL.iter()
+                            .filter(|&&x| adjustments.get(x) != default.get(x))
+                            .map(|&x| adjustments.fmt_value(x))
+                            .collect::<Vec<_>>().join(" "))
                 };
-            }
-            read_char!();
-            let move_len: SizeType = {
-                let num = scale.ceil() as SizeType;
-                if num == 0 {
-                    1
+                let tone_status: String = if tone_mapping.is_identity() {
+                    String::new()
                 } else {
-                    num
+                    format!("HDR[{}] ", tone_mapping.fmt_status())
+                };
+                let hist_status: String = hist.map(|hist| {
+                    format!("Hist[{} {}] ",
+                            if hist_viewport { "View" } else { "Whole" },
+                            hist.fmt_stats())
+                }).unwrap_or_default();
+                let source_status: String = source.as_ref()
+                    .map(ImageSource::fmt_status)
+                    .unwrap_or_default()
+                    + &slideshow.map(|x| x.fmt_status()).unwrap_or_default();
+                let colormap_status: String = colormap.map(|colormap| {
+                    format!("{}[{}] ",
+                            colormap.name(),
+                            colormap.legend(16, colormap_window))
+                }).unwrap_or_default();
+                let net_status: String = if show_stats || adaptive {
+                    stats.fmt_status()
+                        + &budget.filter(|_| adaptive)
+                            .map(|budget| format!("Auto[{}] ", budget))
+                            .unwrap_or_default()
+                } else {
+                    String::new()
+                };
+                let status_line: String = format!(concat!(
+                        "\x1b[?7l\x1b[7m", // 关闭自动换行, 过长的状态行将被截断
+                        "{}",
+                        "ImgSize[{}x{}] ",
+                        "Pos[{},{}] ",
+                        "Ratio[{:.2}] ",
+                        "Opt[{}] ",
+                        "Fl[{}] ",
+                        "{}",
+                        "{}",
+                        "{}",
+                        "{}",
+                        "{}",
+                        "{}",
+                        "Help(H) ",
+                        "Quit(Q)",
+                        "\x1b[0m\x1b[s{}\x1b[K\x1b[u\x1b[?7h"),
+                        source_status,
+                        img_size.x, img_size.y,
+                        win_pos.x, win_pos.y,
+                        scale,
+                        screen_buf.cfg.chromatic_aberration,
+                        filter_idx,
+                        net_status,
+                        adjust_status,
+                        tone_status,
+                        inspect_status,
+                        hist_status,
+                        colormap_status,
+                        error_buf);
+                error_buf.clear();
+                let write_start: Instant = Instant::now();
+                eprint!("{}{}", frame_output, status_line);
+                last_render = Instant::now();
+                stats.record(frame_output.len() + status_line.len(),
+                             render_start.elapsed(),
+                             write_start.elapsed());
+                // 预览与其后的完整质量合计为一帧的用量
+                if preview {
+                    preview_usage = (stats.bytes(), stats.write_time());
+                } else if let Some(budget) = budget.filter(|_| {
+                    adaptive && fresh && ! ascii_mode
+                }) {
+                    let (bytes, time) = mem::take(&mut preview_usage);
+                    screen_buf.cfg.chromatic_aberration = budget.next_opt(
+                        screen_buf.cfg.chromatic_aberration,
+                        stats.bytes() + bytes,
+                        stats.write_time() + time);
                 }
-            };
+                is_start = false;
+                preview_shown = preview;
+            }
             macro_rules! ctrl_err {
                 ( $( $x:expr ),* ) => {
                     error_buf.extend(
@@ -332,19 +1087,109 @@ pub fn run(matches: ArgMatches) {
                                 format!( $( $x ),* )).chars())
                 };
             }
-            /// <: new < old
-            /// >: new > old
+            macro_rules! ctrl_info {
+                ( $( $x:expr ),* ) => {
+                    error_buf.extend(
+                        format!(" \x1b[7m{}\x1b[0m",
+                                format!( $( $x ),* )).chars())
+                };
+            }
+            macro_rules! read_char {
+                () => {
+                    tty_input.read_exact(&mut readbuf).unwrap_or_else(|e| {
+                        log!(e:(2) "ReadCharError: {}", e)
+                    })
+                };
+            }
+            /// 从来源重新读取图片, 换为大小不同的另一个图片时重新适应终端
+            macro_rules! reload_source {
+                () => {
+                    let source = source.as_mut().unwrap();
+                    let is_new = source.shown() != source.path();
+                    match source.load().unwrap() {
+                        Ok(img) => {
+                            let old_size = img_size;
+                            set_image!(img);
+                            if is_new && img_size != old_size {
+                                refit!();
+                            }
+                            if ! is_new {
+                                ctrl_info!("Reloaded");
+                            }
+                        },
+                        // 可能仍在写入, 保留之前的图片并等待下一次修改
+                        Err(e) => ctrl_err!("ReloadError: {}", e),
+                    }
+                };
+            }
+            /// 切换到前一个或后一个文件
+            macro_rules! step_source {
+                ( $step:expr ) => {
+                    if source.as_mut().is_some_and(|source| source.step($step)) {
+                        if let Some(slideshow) = &mut slideshow {
+                            slideshow.restart();
+                        }
+                        reload_source!();
+                    } else {
+                        ctrl_err!("NoOtherImage");
+                    }
+                };
+            }
+            if preview_shown && ! tty_input.poll(Some(Duration::ZERO)) {
+                refine = true;
+                continue;
+            }
+            match wait_event(&tty_input,
+                             frames.as_ref(),
+                             source.as_mut(),
+                             slideshow.and_then(|x| x.deadline())) {
+                Event::Input => read_char!(),
+                Event::SourceChanged => {
+                    from_source = true;
+                    reload_source!();
+                    continue;
+                },
+                Event::Advance => {
+                    from_source = true;
+                    step_source!(1);
+                    continue;
+                },
+                Event::Frame(frame) => {
+                    from_source = true;
+                    let old_size = img_size;
+                    set_image!(frame);
+                    if img_size != old_size {
+                        refit!(); // 大小改变时仅重新初始化缩放与位置
+                    }
+                    continue;
+                },
+                Event::StreamEnd(e) => {
+                    frames = None;
+                    match e {
+                        Some(e) => ctrl_err!("StreamError: {}", e),
+                        None => ctrl_info!("StreamEnd"),
+                    }
+                    continue;
+                },
+            }
+            let [move_wlen, move_hlen]: [SizeType; 2] = [
+                scale, scale * pixel_aspect
+            ].map(|x| (x.ceil() as SizeType).max(1));
+            /// `<`: new < old
+            ///
+            /// `>`: new > old
+            ///
             /// note 一轮中仅可运行一次,
             /// 并且仅在 scale_term_size 未改变, scale 已改变时使用
             macro_rules! fix_pos {
                 (<) => {{
                     win_pos += (scale_term_size
-                                - term_size.mul_scale(scale))
+                                - get_view_size(term_size, scale, pixel_aspect))
                         >> 1.into();
                 }};
                 (>) => {{
                     let old: Position = win_pos;
-                    win_pos -= (term_size.mul_scale(scale)
+                    win_pos -= (get_view_size(term_size, scale, pixel_aspect)
                                 - scale_term_size)
                         >> 1.into();
                     if win_pos.x > old.x { win_pos.x = 0 }
@@ -359,32 +1204,38 @@ pub fn run(matches: ArgMatches) {
                 (scale_term_size.x as Float * long_move_ratio).ceil() as SizeType,
                 (scale_term_size.y as Float * long_move_ratio).ceil() as SizeType
             ];
+            if adaptive && b"oOiI".contains(&readbuf[0]) {
+                adaptive = false; // 手动调整 opt 时暂停自适应
+                ctrl_info!("AdaptiveOff");
+            }
             // 将在此处阻塞等待输入
             match readbuf[0] as char { // 处理读入的单个字符
                 'r' => {
-                    screen_buf.init_bg_colors();
-                    clear_screen!();
+                    redraw!();
+                },
+                'R' => {
+                    query_term = true;
+                    continue 'main
                 },
-                'R' => continue 'main,
                 'Q' | '\x03' => break, /* exit */
                 'h' => {
                     let old = win_pos.x;
-                    win_pos.x -= move_len;
+                    win_pos.x -= move_wlen;
                     if win_pos.x > old {
                         win_pos.x = 0;
                         ctrl_err!("RB");
                     }
                 },
-                'j' => win_pos.y += move_len,
+                'j' => win_pos.y += move_hlen,
                 'k' => {
                     let old = win_pos.y;
-                    win_pos.y -= move_len;
+                    win_pos.y -= move_hlen;
                     if win_pos.y > old {
                         win_pos.y = 0;
                         ctrl_err!("RB");
                     }
                 },
-                'l' => win_pos.x += move_len,
+                'l' => win_pos.x += move_wlen,
 
                 'a' => {
                     let old = win_pos.x;
@@ -472,12 +1323,96 @@ pub fn run(matches: ArgMatches) {
                     filter_idx += 1;
                     filter_idx %= FILTERS.len();
                 },
-                'g' => repr_img = repr_img.fliph(),
-                'G' => repr_img = repr_img.flipv(),
-                'y' => repr_img = repr_img.rotate90(),
-                'Y' => repr_img = repr_img.rotate270(),
+                'g' | 'G' | 'y' | 'Y' => {
+                    repr_img = match readbuf[0] {
+                        b'g' => { orientation.fliph(); repr_img.fliph() },
+                        b'G' => { orientation.flipv(); repr_img.flipv() },
+                        b'y' => { orientation.rotate90(); repr_img.rotate90() },
+                        _ => { orientation.rotate270(); repr_img.rotate270() },
+                    };
+                    img_size = Position::from([repr_img.width(), repr_img.height()]);
+                    clahe = None; // 块的位置已经改变
+                    clear_caches!();
+                },
+                'b' => show_hist = ! show_hist,
+                'B' => hist_viewport = ! hist_viewport,
+                'v' => colormap = Colormap::cycle(colormap),
+                'V' => colormap_window = [0, 255],
+                '{' | '}' => colormap_window = step_window(
+                    colormap_window, 0, if readbuf[0] == b'}' { 8 } else { -8 }),
+                '(' | ')' => colormap_window = step_window(
+                    colormap_window, 1, if readbuf[0] == b')' { 8 } else { -8 }),
+                'u' => {
+                    enhance = enhance.next();
+                    ctrl_info!("Enhance: {}", enhance.name());
+                },
+                't' => {
+                    ascii_mode = ! ascii_mode;
+                    redraw!();
+                },
+                'T' => ascii_art.edges = ! ascii_art.edges,
+                'L' => linear_light = ! linear_light,
+                'e' | 'E' => {
+                    adjustment = if readbuf[0] == b'e' {
+                        adjustment.next()
+                    } else {
+                        adjustment.prev()
+                    };
+                    ctrl_info!("{}", adjustments.fmt_value(adjustment));
+                },
+                '[' | ']' => {
+                    adjustments.step(
+                        adjustment, if readbuf[0] == b']' { 1 } else { -1 });
+                    ctrl_info!("{}", adjustments.fmt_value(adjustment));
+                },
+                '0' => {
+                    adjustments = Adjustments::new();
+                    tone_mapping = ToneMapping::new();
+                },
+                '<' | '>' => {
+                    tone_mapping.step_exposure(
+                        if readbuf[0] == b'>' { 1 } else { -1 });
+                },
+                ';' | '\'' => {
+                    tone_mapping.step_offset(
+                        if readbuf[0] == b'\'' { 1 } else { -1 });
+                },
+                'P' => tone_mapping.operator = tone_mapping.operator.next(),
+                'p' => show_inspector = ! show_inspector,
+                ',' | '.' => step_source!(if readbuf[0] == b'.' { 1 } else { -1 }),
+                ' ' => match &mut slideshow {
+                    Some(slideshow) => slideshow.toggle(),
+                    None => ctrl_err!("NoSlideshow"),
+                },
+                'J' | 'K' => match &mut slideshow {
+                    Some(slideshow) => slideshow.step_interval(
+                        if readbuf[0] == b'K' { 1 } else { -1 }),
+                    None => ctrl_err!("NoSlideshow"),
+                },
+                'F' => match source.as_mut() {
+                    Some(src) if src.is_watching() => {
+                        if src.toggle_pause() {
+                            reload_source!();
+                        } else if src.is_paused() {
+                            ctrl_info!("Paused");
+                        }
+                    },
+                    _ => ctrl_err!("NotWatching"),
+                },
+                'N' => show_stats = ! show_stats,
+                'U' => match budget {
+                    Some(_) => {
+                        adaptive = ! adaptive;
+                        ctrl_info!("Adaptive: {}", if adaptive { "on" } else { "off" });
+                    },
+                    None => ctrl_err!("NoBudget"),
+                },
                 'm' => invert = ! invert,
                 'M' => grayscale = ! grayscale,
+                'n' => {
+                    channel_view = channel_view.next();
+                    ctrl_info!("Channel: {}", channel_view.short_name());
+                },
                 'X' => {
                     init_scale!();
                     init_win_pos!();
@@ -516,28 +1451,55 @@ pub fn run(matches: ArgMatches) {
                             "move 3/4 term: `ASWD`, s/l ratio: ({:.2},{:.2})"),
                             short_move_ratio, long_move_ratio;
                         "Opt: add opt: `oO`, sub opt: `iI`";
+                        "Stats: `N`, adaptive opt: `U` (with `--adaptive`)";
                         "Zoom: `cx` or `+-`, ratio: {:.4},{:.4}",
                             zoom_add_ratio, zoom_sub_ratio;
                         "ReDraw: `r`";
                         "ReInit: `R`";
                         "SwitchBackground: `z` [{}]", bgs_fmt;
                         "InitBackground: `Z`";
+                        "TerminalBackground: {}",
+                            Color::Rgb(term_back_ground).fmt_color();
                         "SetFilter: `f`, ({:?}) {:?}",
                             FILTERS[filter_idx], FILTERS;
                         "FlipImage: `gG`";
                         "Rotate: `yY`";
                         "Invert: `m`";
                         "Grayscale: `M`";
+                        "Channel: `n` (all, R, G, B, alpha, alpha mask) [{}]",
+                            channel_view.short_name();
+                        "Enhance: `u` (off, auto-levels, CLAHE) [{}]",
+                            enhance.name();
+                        concat!(
+                            "Colormap: `v` (viridis magma inferno jet turbo), ",
+                            "window min: `{{}}`, max: `()`, reset: `V`");
+                        "Histogram: `b`, whole image / viewport: `B`";
+                        "LinearLight: `L` [{}]", linear_light;
+                        concat!(
+                            "Adjust: select `eE`, sub/add `[]`, reset `0`, ",
+                            "(brightness contrast gamma hue saturation)");
+                        concat!(
+                            "HDR: exposure `<>`, offset `;'`, ",
+                            "tonemap `P` (clamp reinhard aces) [{}]"),
+                            tone_mapping.operator.name();
+                        "PixelInspector: `p`";
+                        "PauseWatching: `F` (with `--watch` or a directory)";
+                        concat!(
+                            "Images: previous/next `,.`, slideshow `Space`, ",
+                            "shorter/longer interval `JK`");
+                        "AsciiMode: `t`, edges: `T` [{:?}]",
+                            ascii_art.ramp.iter().collect::<String>();
                         "ThisHelpInfo: `H?`";
                         "InitRatio: `X`";
                         "UnitRatio: `C`";
+                        "CellAspect: {:.3} (pixel {:.3})",
+                            pixel_aspect * 2.0, pixel_aspect;
                         "Quit: `Q` or `Ctrl-C`";
                     };
                     eprint!("\x1b[{}H", (term_size.y >> 1) + 1);
 
                     read_char!();
-                    clear_screen!();
-                    screen_buf.init_bg_colors();
+                    redraw!();
                 },
                 c => {
                     ctrl_err!("EI:{:?}", c)
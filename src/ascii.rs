@@ -0,0 +1,126 @@
+use image::GrayImage;
+
+use super::*;
+
+/// 默认字符梯度, 由暗到亮
+pub const DEFAULT_RAMP: &str = " .:-=+*#%@";
+
+/// 边缘字符判定的默认梯度阈值 (Sobel 幅值)
+pub const DEFAULT_EDGE_THRESHOLD: Float = 384.0;
+
+/// 将灰度图片转换为字符画
+///
+/// 每个字符对应纵向相邻的两个像素, 与半块字符相同,
+/// 因此可以与彩色输出使用相同的裁剪与缩放
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsciiArt {
+    /// 字符梯度, 由暗到亮
+    pub ramp: Vec<char>,
+    /// 在明显的边缘处使用 `-|/\`
+    pub edges: bool,
+    /// 边缘的梯度阈值
+    pub edge_threshold: Float,
+}
+impl AsciiArt {
+    /// # Examples
+    /// ```
+    /// use timg::AsciiArt;
+    /// let art = AsciiArt::new(" .#");
+    /// assert_eq!(art.ramp, vec![' ', '.', '#']);
+    /// assert!(! art.edges);
+    /// ```
+    pub fn new(ramp: &str) -> Self {
+        let mut ramp: Vec<char> = ramp.chars().collect();
+        if ramp.is_empty() {
+            ramp = DEFAULT_RAMP.chars().collect();
+        }
+        Self {
+            ramp,
+            edges: false,
+            edge_threshold: DEFAULT_EDGE_THRESHOLD,
+        }
+    }
+    /// 从亮度获取字符
+    /// # Examples
+    /// ```
+    /// use timg::AsciiArt;
+    /// let art = AsciiArt::new(" .:#");
+    /// assert_eq!(art.luma_char(0), ' ');
+    /// assert_eq!(art.luma_char(100), '.');
+    /// assert_eq!(art.luma_char(255), '#');
+    /// ```
+    pub fn luma_char(&self, luma: u8) -> char {
+        let idx = luma as usize * self.ramp.len() / 256;
+        self.ramp[idx]
+    }
+    /// 根据梯度方向获取边缘字符, 梯度不够大时返回 None
+    fn edge_char(&self, gx: Float, gy: Float) -> Option<char> {
+        if gx.hypot(gy) < self.edge_threshold {
+            return None;
+        }
+        // 边缘方向与梯度方向垂直, 注意 y 轴向下
+        let angle = gy.atan2(gx).to_degrees().rem_euclid(180.0);
+        Some(match angle {
+            a if !(22.5..157.5).contains(&a) => '|',
+            a if a < 67.5 => '/',
+            a if a < 112.5 => '-',
+            _ => '\\',
+        })
+    }
+    /// 渲染为多行文本, 每行对应两行像素
+    pub fn render(&self, img: &GrayImage) -> Vec<String> {
+        let (width, height) = img.dimensions();
+        let luma = |x: i64, y: i64| -> Float {
+            let x = x.clamp(0, width as i64 - 1) as u32;
+            let y = y.clamp(0, height as i64 - 1) as u32;
+            img.get_pixel(x, y).0[0] as Float
+        };
+        let sobel = |x: i64, y: i64| -> (Float, Float) {
+            let gx = luma(x + 1, y - 1) + 2.0 * luma(x + 1, y) + luma(x + 1, y + 1)
+                - luma(x - 1, y - 1) - 2.0 * luma(x - 1, y) - luma(x - 1, y + 1);
+            let gy = luma(x - 1, y + 1) + 2.0 * luma(x, y + 1) + luma(x + 1, y + 1)
+                - luma(x - 1, y - 1) - 2.0 * luma(x, y - 1) - luma(x + 1, y - 1);
+            (gx, gy)
+        };
+        (0..height).step_by(2).map(|y| {
+            (0..width).map(|x| {
+                let (x, y) = (x as i64, y as i64);
+                let lower = if y + 1 < height as i64 { y + 1 } else { y };
+                if self.edges {
+                    let (gx1, gy1) = sobel(x, y);
+                    let (gx2, gy2) = sobel(x, lower);
+                    if let Some(c) = self.edge_char(
+                        (gx1 + gx2) / 2.0, (gy1 + gy2) / 2.0) {
+                        return c;
+                    }
+                }
+                let avg = (luma(x, y) + luma(x, lower)) / 2.0;
+                self.luma_char(avg.round() as u8)
+            }).collect()
+        }).collect()
+    }
+}
+impl Default for AsciiArt {
+    fn default() -> Self {
+        Self::new(DEFAULT_RAMP)
+    }
+}
+
+#[test]
+fn render_test() {
+    use image::Luma;
+
+    let mut art = AsciiArt::new(" #");
+    let img = GrayImage::from_fn(4, 4, |x, _| {
+        Luma([if x < 2 { 0 } else { 255 }])
+    });
+    assert_eq!(art.render(&img), vec!["  ##", "  ##"]);
+
+    art.edges = true;
+    assert_eq!(art.render(&img), vec![" ||#", " ||#"]);
+
+    let img = GrayImage::from_fn(3, 4, |_, y| {
+        Luma([if y < 2 { 0 } else { 255 }])
+    });
+    assert_eq!(art.render(&img), vec!["---", "---"]);
+}
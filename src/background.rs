@@ -0,0 +1,126 @@
+use image::RgbaImage;
+use term_lattice::{
+    types::Rgb,
+    Color,
+};
+
+use super::*;
+
+/// 棋盘格的格子大小所使用的单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckerUnit {
+    /// 源图片的像素, 格子随缩放而缩放
+    Source,
+    /// 屏幕上的虚拟像素, 格子大小不随缩放变化
+    Screen,
+}
+
+/// Checkerboard background, make transparent areas obvious.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checker {
+    pub colors: [Rgb; 2],
+    /// 格子边长
+    pub size: SizeType,
+    pub unit: CheckerUnit,
+}
+impl Checker {
+    pub fn new(colors: [Rgb; 2], size: SizeType, unit: CheckerUnit) -> Self {
+        Self { colors, size: size.max(1), unit }
+    }
+    /// 获取位置所在格子的颜色, 位置的单位须与 `self.unit` 一致
+    /// # Examples
+    /// ```
+    /// use timg::{Checker, CheckerUnit};
+    /// let checker = Checker::new([[0; 3], [255; 3]], 2, CheckerUnit::Screen);
+    /// assert_eq!(checker.color_at(0, 0), [0; 3]);
+    /// assert_eq!(checker.color_at(1, 1), [0; 3]);
+    /// assert_eq!(checker.color_at(2, 1), [255; 3]);
+    /// assert_eq!(checker.color_at(2, 3), [0; 3]);
+    /// ```
+    pub fn color_at(&self, x: SizeType, y: SizeType) -> Rgb {
+        self.colors[((x / self.size + y / self.size) & 1) as usize]
+    }
+}
+impl Default for Checker {
+    fn default() -> Self {
+        Self::new([[0x66; 3], [0x99; 3]], 4, CheckerUnit::Screen)
+    }
+}
+
+/// 图片透明部分的背景
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Background {
+    /// 纯色背景, 为 `Color::None` 时使用终端的背景
+    Color(Color),
+    Checker(Checker),
+}
+impl Background {
+    /// 图片以外区域所使用的颜色
+    pub fn fill_color(&self) -> Color {
+        match self {
+            Self::Color(color) => *color,
+            Self::Checker(_) => Color::None,
+        }
+    }
+}
+impl From<Color> for Background {
+    fn from(value: Color) -> Self {
+        Self::Color(value)
+    }
+}
+impl FmtColor for Background {
+    /// # Examples
+    /// ```
+    /// # use term_lattice::Color;
+    /// # use timg::{Background, Checker, FmtColor};
+    /// assert_eq!(Background::Color(Color::None).fmt_color(), "None");
+    /// assert_eq!(Background::Checker(Checker::default()).fmt_color(),
+    ///     concat!("Checker(\x1b[48;2;102;102;102m#\x1b[49m666666/",
+    ///             "\x1b[48;2;153;153;153m#\x1b[49m999999,4px)"));
+    /// ```
+    fn fmt_color(&self) -> String {
+        match self {
+            Self::Color(color) => color.fmt_color(),
+            Self::Checker(Checker { colors: [a, b], size, unit }) => {
+                format!("Checker({}/{},{}{})",
+                        Color::Rgb(*a).fmt_color(),
+                        Color::Rgb(*b).fmt_color(),
+                        size,
+                        match unit {
+                            CheckerUnit::Source => "src",
+                            CheckerUnit::Screen => "px",
+                        })
+            },
+        }
+    }
+}
+
+/// Blend a frame over its background on up to `threads` threads.
+///
+/// `back_ground` gives the background color at a position of the frame,
+/// the result is in the order of the pixels.
+/// # Examples
+/// ```
+/// use image::{Rgba, RgbaImage};
+/// use timg::{composite, rgba_to_rgb, Checker, CheckerUnit};
+/// let img = RgbaImage::from_pixel(4, 2, Rgba([200, 100, 0, 0]));
+/// let checker = Checker::new([[0; 3], [255; 3]], 1, CheckerUnit::Screen);
+/// let rgbs = composite(&img, 2, rgba_to_rgb, |x, y| checker.color_at(x, y));
+/// assert_eq!(rgbs[..5], [[0; 3], [255; 3], [0; 3], [255; 3], [255; 3]]);
+/// ```
+pub fn composite<F>(img: &RgbaImage,
+                    threads: usize,
+                    blend: fn(Rgba, Rgb) -> Rgb,
+                    back_ground: F) -> Vec<Rgb>
+where F: Fn(SizeType, SizeType) -> Rgb + Sync,
+{
+    let width = img.width() as usize;
+    let mut res = vec![[0; 3]; width * img.height() as usize];
+    par_bands(&mut res, width, threads, |first, band| {
+        for (i, rgb) in band.iter_mut().enumerate() {
+            let [x, y] = [(i % width) as SizeType, (first + i / width) as SizeType];
+            *rgb = blend(img.get_pixel(x, y).0, back_ground(x, y));
+        }
+    });
+    res
+}
@@ -0,0 +1,225 @@
+use std::{
+    error::Error,
+    fmt,
+    str::FromStr,
+    time::Duration,
+};
+
+/// 平滑时新的一帧所占的权重
+const SMOOTHING: f64 = 0.25;
+/// 自适应时 opt 的上限, 更高时几乎不再输出变化
+pub const MAX_ADAPTIVE_OPT: u32 = 400;
+
+/// 以 1024 进制格式化字节数
+/// # Examples
+/// ```
+/// use timg::fmt_bytes;
+/// assert_eq!(fmt_bytes(512.0), "512B");
+/// assert_eq!(fmt_bytes(1536.0), "1.5K");
+/// assert_eq!(fmt_bytes(3.0 * 1024.0 * 1024.0), "3.0M");
+/// ```
+pub fn fmt_bytes(bytes: f64) -> String {
+    const K: f64 = 1024.0;
+    if bytes < K {
+        format!("{}B", bytes.round())
+    } else if bytes < K * K {
+        format!("{:.1}K", bytes / K)
+    } else {
+        format!("{:.1}M", bytes / (K * K))
+    }
+}
+
+/// Bytes written per frame and a running throughput estimate.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BandwidthStats {
+    /// 上一帧的字节数
+    bytes: usize,
+    /// 上一帧从开始渲染到写完的时间
+    frame_time: Duration,
+    /// 上一帧写出的耗时
+    write_time: Duration,
+    /// 平滑后的每秒写出字节数
+    throughput: Option<f64>,
+}
+impl BandwidthStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// 记录一帧, `write_time` 为写出的耗时, 终端或连接较慢时写出会阻塞
+    pub fn record(&mut self,
+                  bytes: usize,
+                  frame_time: Duration,
+                  write_time: Duration) {
+        self.bytes = bytes;
+        self.frame_time = frame_time;
+        self.write_time = write_time;
+        let secs = write_time.as_secs_f64();
+        if secs > 0.0 {
+            let rate = bytes as f64 / secs;
+            self.throughput = Some(match self.throughput {
+                Some(x) => x + (rate - x) * SMOOTHING,
+                None => rate,
+            });
+        }
+    }
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+    pub fn frame_time(&self) -> Duration {
+        self.frame_time
+    }
+    pub fn write_time(&self) -> Duration {
+        self.write_time
+    }
+    /// # Examples
+    /// ```
+    /// # use std::time::Duration;
+    /// use timg::BandwidthStats;
+    /// let mut stats = BandwidthStats::new();
+    /// assert_eq!(stats.fmt_status(), "Net[0B/f 0ms -/s] ");
+    /// stats.record(2048, Duration::from_millis(40), Duration::from_millis(20));
+    /// assert_eq!(stats.fmt_status(), "Net[2.0K/f 40ms 100.0K/s] ");
+    /// ```
+    pub fn fmt_status(&self) -> String {
+        format!("Net[{}/f {}ms {}/s] ",
+                fmt_bytes(self.bytes as f64),
+                self.frame_time.as_millis(),
+                self.throughput.map_or("-".to_string(), fmt_bytes))
+    }
+}
+
+/// Budget of a frame the adaptive mode tunes the opt level toward.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    /// 每帧写出的字节数
+    Bytes(usize),
+    /// 每帧写出的时间, 不包括缩放与处理, 这部分不受 opt 影响
+    Time(Duration),
+}
+impl Budget {
+    /// 根据上一帧的用量得到下一帧的 opt,
+    /// 超出预算时按比例提高, 远低于预算时逐步降低
+    /// # Examples
+    /// ```
+    /// # use std::time::Duration;
+    /// use timg::Budget;
+    /// let budget = Budget::Bytes(10000);
+    /// let time = Duration::ZERO;
+    /// assert_eq!(budget.next_opt(60, 15000, time), 90);
+    /// assert_eq!(budget.next_opt(60, 80000, time), 120);
+    /// assert_eq!(budget.next_opt(60, 8000, time), 60);
+    /// assert_eq!(budget.next_opt(60, 1000, time), 45);
+    /// assert_eq!(budget.next_opt(1, 1000, time), 1);
+    /// let budget = Budget::Time(Duration::from_millis(50));
+    /// assert_eq!(budget.next_opt(0, 0, Duration::from_millis(100)), 1);
+    /// ```
+    pub fn next_opt(&self, opt: u32, bytes: usize, write_time: Duration) -> u32 {
+        let ratio = match *self {
+            Self::Bytes(max) => bytes as f64 / max.max(1) as f64,
+            Self::Time(max) => write_time.as_secs_f64()
+                / max.as_secs_f64().max(f64::EPSILON),
+        };
+        if ratio > 1.1 {
+            ((opt as f64 * ratio.min(2.0)).ceil() as u32)
+                .max(opt + 1)
+                .min(MAX_ADAPTIVE_OPT)
+        } else if ratio < 0.5 {
+            opt.saturating_sub((opt / 4).max(1)).max(1)
+        } else {
+            opt
+        }
+    }
+}
+impl FromStr for Budget {
+    type Err = BudgetError;
+    /// 字节数可以带有 `K` `M` 后缀, 时间以 `ms` 或 `s` 结尾
+    /// # Examples
+    /// ```
+    /// # use std::time::Duration;
+    /// use timg::Budget;
+    /// assert_eq!("20K".parse(), Ok(Budget::Bytes(20 * 1024)));
+    /// assert_eq!("1.5m".parse(), Ok(Budget::Bytes(1572864)));
+    /// assert_eq!("800".parse(), Ok(Budget::Bytes(800)));
+    /// assert_eq!("50ms".parse(), Ok(Budget::Time(Duration::from_millis(50))));
+    /// assert_eq!("0.2s".parse(), Ok(Budget::Time(Duration::from_millis(200))));
+    /// assert!("0".parse::<Budget>().is_err());
+    /// assert!("fast".parse::<Budget>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || BudgetError(s.to_string());
+        let lower = s.trim().to_ascii_lowercase();
+        let num = |x: &str| -> Result<f64, BudgetError> {
+            let num: f64 = x.trim().parse().map_err(|_| err())?;
+            if num > 0.0 && num.is_finite() { Ok(num) } else { Err(err()) }
+        };
+        let secs = |secs: f64| Duration::try_from_secs_f64(secs).map_err(|_| err());
+        let res = if let Some(x) = lower.strip_suffix("ms") {
+            Self::Time(secs(num(x)? / 1000.0)?)
+        } else if let Some(x) = lower.strip_suffix('s') {
+            Self::Time(secs(num(x)?)?)
+        } else if let Some(x) = lower.strip_suffix('k') {
+            Self::Bytes((num(x)? * 1024.0) as usize)
+        } else if let Some(x) = lower.strip_suffix('m') {
+            Self::Bytes((num(x)? * 1024.0 * 1024.0) as usize)
+        } else {
+            Self::Bytes(num(&lower)? as usize)
+        };
+        match res {
+            Self::Bytes(0) => Err(err()),
+            Self::Time(x) if x.is_zero() => Err(err()),
+            res => Ok(res),
+        }
+    }
+}
+impl fmt::Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bytes(x) => write!(f, "{}/f", fmt_bytes(*x as f64)),
+            Self::Time(x) => write!(f, "{}ms", x.as_millis()),
+        }
+    }
+}
+
+/// 预算字符串的格式错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetError(pub String);
+impl fmt::Display for BudgetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is not a budget like `20K` bytes or `50ms`", self.0)
+    }
+}
+impl Error for BudgetError {}
+
+#[test]
+fn budget_test() {
+    let parse = |s: &str| s.parse::<Budget>();
+    let err = |s: &str| Err(BudgetError(s.to_string()));
+    assert_eq!(parse(" 2k "), Ok(Budget::Bytes(2048)));
+    assert_eq!(parse("1e30s"), err("1e30s"));
+    assert_eq!(parse("1e30ms"), err("1e30ms"));
+    assert_eq!(parse("1e30"), Ok(Budget::Bytes(usize::MAX)));
+    assert_eq!(parse("0.1"), err("0.1"));
+    assert_eq!(parse("1e-12s"), err("1e-12s"));
+    assert_eq!(parse("-5ms"), err("-5ms"));
+    assert_eq!(parse("nanms"), err("nanms"));
+    assert_eq!(parse("infk"), err("infk"));
+    assert_eq!(parse(""), err(""));
+    assert_eq!(parse("ms"), err("ms"));
+
+    let time = Duration::ZERO;
+    let budget = Budget::Bytes(100);
+    // 上限与下限
+    assert_eq!(budget.next_opt(MAX_ADAPTIVE_OPT, 1000, time), MAX_ADAPTIVE_OPT);
+    assert_eq!(budget.next_opt(300, 1000, time), MAX_ADAPTIVE_OPT);
+    assert_eq!(budget.next_opt(1, 0, time), 1);
+    assert_eq!(budget.next_opt(0, 0, time), 1);
+    assert_eq!(budget.next_opt(0, 111, time), 1);
+    // 在预算附近时不变
+    assert_eq!(budget.next_opt(60, 110, time), 60);
+    assert_eq!(budget.next_opt(60, 50, time), 60);
+    // 时间预算只比较写出的时间, 不受字节数影响
+    let budget = Budget::Time(Duration::from_millis(10));
+    assert_eq!(budget.next_opt(60, usize::MAX, Duration::from_millis(9)), 60);
+    assert_eq!(budget.next_opt(60, 0, Duration::from_millis(30)), 120);
+    assert_eq!(budget.next_opt(60, 0, Duration::ZERO), 45);
+}
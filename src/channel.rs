@@ -0,0 +1,119 @@
+use std::ops::DerefMut;
+
+use image::{
+    ImageBuffer,
+    Pixel,
+};
+use term_lattice::types::Rgb;
+
+use super::*;
+
+/// 透明度遮罩的默认颜色
+pub const DEFAULT_MASK_COLOR: Rgb = [255, 0, 0];
+
+/// Show a single channel of the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelView {
+    /// 正常显示所有通道
+    All,
+    Red,
+    Green,
+    Blue,
+    /// 透明度显示为灰度
+    Alpha,
+    /// 忽略透明度显示颜色, 并在透明的部分覆盖半透明的纯色
+    AlphaMask,
+}
+impl ChannelView {
+    pub const ALL: [Self; 6] = [
+        Self::All, Self::Red, Self::Green, Self::Blue,
+        Self::Alpha, Self::AlphaMask,
+    ];
+    /// # Examples
+    /// ```
+    /// use timg::ChannelView;
+    /// assert_eq!(ChannelView::All.next(), ChannelView::Red);
+    /// assert_eq!(ChannelView::AlphaMask.next(), ChannelView::All);
+    /// ```
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|&x| x == self).unwrap();
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Self::All => "RGBA",
+            Self::Red => "R",
+            Self::Green => "G",
+            Self::Blue => "B",
+            Self::Alpha => "A",
+            Self::AlphaMask => "Mask",
+        }
+    }
+    /// 转换未预乘透明度的 RGBA 图片, 除 [`Self::All`] 以外的结果均为不透明的
+    /// # Examples
+    /// ```
+    /// # use image::{Rgba, Rgba32FImage, RgbaImage};
+    /// use timg::ChannelView;
+    /// let mut img = RgbaImage::from_pixel(1, 1, Rgba([10, 20, 30, 40]));
+    /// ChannelView::Green.apply(&mut img, [0; 3]);
+    /// assert_eq!(img.get_pixel(0, 0).0, [20, 20, 20, 255]);
+    /// let mut img = Rgba32FImage::from_pixel(1, 1, Rgba([0.5, 2.0, 0.0, 0.0]));
+    /// ChannelView::Green.apply(&mut img, [0; 3]);
+    /// assert_eq!(img.get_pixel(0, 0).0, [2.0, 2.0, 2.0, 1.0]);
+    /// ```
+    pub fn apply<P, C>(&self,
+                       img: &mut ImageBuffer<P, C>,
+                       mask_color: Rgb)
+    where P: Pixel,
+          C: DerefMut<Target = [P::Subpixel]>,
+          P::Subpixel: Sample,
+    {
+        type S<P> = <P as Pixel>::Subpixel;
+        assert_eq!(P::CHANNEL_COUNT, 4, "need a RGBA image");
+        let opaque = S::<P>::from_f32(S::<P>::MAX);
+        let idx = match self {
+            Self::All => return,
+            Self::Red => 0,
+            Self::Green => 1,
+            Self::Blue => 2,
+            Self::Alpha => 3,
+            Self::AlphaMask => {
+                let max = S::<P>::MAX;
+                for pixel in img.chunks_mut(4) {
+                    // 完全透明时遮罩为一半的不透明度
+                    let alpha = ((max - pixel[3].into()) / (2.0 * max)).clamp(0.0, 0.5);
+                    for (x, mask) in pixel.iter_mut().zip(mask_color) {
+                        let mask = mask as f32 / 255.0 * max;
+                        *x = S::<P>::from_f32((*x).into() * (1.0 - alpha) + mask * alpha);
+                    }
+                    pixel[3] = opaque;
+                }
+                return;
+            },
+        };
+        for pixel in img.chunks_mut(4) {
+            let value = pixel[idx];
+            pixel.copy_from_slice(&[value, value, value, opaque]);
+        }
+    }
+}
+
+#[test]
+fn channel_view_test() {
+    use image::{Rgba, RgbaImage};
+
+    let src = RgbaImage::from_fn(2, 1, |x, _| {
+        Rgba([10, 20, 30, if x == 0 { 0 } else { 255 }])
+    });
+    let apply = |view: ChannelView| {
+        let mut img = src.clone();
+        view.apply(&mut img, [255, 0, 0]);
+        img
+    };
+    assert_eq!(apply(ChannelView::All), src);
+    assert_eq!(apply(ChannelView::Alpha).get_pixel(0, 0).0, [0, 0, 0, 255]);
+    assert_eq!(apply(ChannelView::Alpha).get_pixel(1, 0).0, [255; 4]);
+    let mask = apply(ChannelView::AlphaMask);
+    assert_eq!(mask.get_pixel(0, 0).0, [133, 10, 15, 255]);
+    assert_eq!(mask.get_pixel(1, 0).0, [10, 20, 30, 255]);
+}
@@ -0,0 +1,351 @@
+use std::{
+    error::Error,
+    fmt,
+};
+
+use term_lattice::{
+    types::Rgb,
+    Color,
+};
+
+use super::*;
+
+/// CSS 颜色名, X11 中同名颜色的值与其基本一致
+const COLOR_NAMES: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff), ("antiquewhite", 0xfaebd7), ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4), ("azure", 0xf0ffff), ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4), ("black", 0x000000), ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff), ("blueviolet", 0x8a2be2), ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887), ("cadetblue", 0x5f9ea0), ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e), ("coral", 0xff7f50), ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc), ("crimson", 0xdc143c), ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b), ("darkcyan", 0x008b8b), ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9), ("darkgreen", 0x006400), ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b), ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f), ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc), ("darkred", 0x8b0000), ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f), ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f), ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1), ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493), ("deepskyblue", 0x00bfff), ("dimgray", 0x696969),
+    ("dimgrey", 0x696969), ("dodgerblue", 0x1e90ff), ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0), ("forestgreen", 0x228b22), ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc), ("ghostwhite", 0xf8f8ff), ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520), ("gray", 0x808080), ("green", 0x008000),
+    ("greenyellow", 0xadff2f), ("grey", 0x808080), ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4), ("indianred", 0xcd5c5c), ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0), ("khaki", 0xf0e68c), ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5), ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd), ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080), ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2), ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90), ("lightgrey", 0xd3d3d3), ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a), ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa), ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899), ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0), ("lime", 0x00ff00), ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6), ("magenta", 0xff00ff), ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa), ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3), ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371), ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a), ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585), ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa), ("mistyrose", 0xffe4e1), ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead), ("navy", 0x000080), ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000), ("olivedrab", 0x6b8e23), ("orange", 0xffa500),
+    ("orangered", 0xff4500), ("orchid", 0xda70d6), ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98), ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093), ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9), ("peru", 0xcd853f), ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd), ("powderblue", 0xb0e0e6), ("purple", 0x800080),
+    ("rebeccapurple", 0x663399), ("red", 0xff0000), ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1), ("saddlebrown", 0x8b4513), ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460), ("seagreen", 0x2e8b57), ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d), ("silver", 0xc0c0c0), ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd), ("slategray", 0x708090), ("slategrey", 0x708090),
+    ("snow", 0xfffafa), ("springgreen", 0x00ff7f), ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c), ("teal", 0x008080), ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347), ("turquoise", 0x40e0d0), ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3), ("white", 0xffffff), ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00), ("yellowgreen", 0x9acd32),
+];
+
+/// 解析颜色时的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseColorError {
+    /// 空字符串
+    Empty,
+    /// 十六进制颜色格式错误
+    Hex(String),
+    /// `rgb(...)` 或 `hsl(...)` 格式错误
+    Function(String),
+    /// 256 色的下标超出范围
+    Index(String),
+    /// 未知的颜色名
+    Name(String),
+}
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty color"),
+            Self::Hex(s) => write!(f, "{:?} is not a `#rgb` or `#rrggbb` color", s),
+            Self::Function(s) => write!(f, "{:?} is not a valid color function", s),
+            Self::Index(s) => write!(f, "{:?} is not a 256-color index (0..=255)", s),
+            Self::Name(s) => write!(f, "unknown color name {:?}", s),
+        }
+    }
+}
+impl Error for ParseColorError {}
+
+/// 256 色转换为 RGB, 前 16 色使用 xterm 的默认值
+/// # Examples
+/// ```
+/// use timg::c256_to_rgb;
+/// assert_eq!(c256_to_rgb(1), [0xcd, 0x00, 0x00]);
+/// assert_eq!(c256_to_rgb(16), [0; 3]);
+/// assert_eq!(c256_to_rgb(21), [0x00, 0x00, 0xff]);
+/// assert_eq!(c256_to_rgb(196), [0xff, 0x00, 0x00]);
+/// assert_eq!(c256_to_rgb(244), [0x80; 3]);
+/// assert_eq!(c256_to_rgb(255), [0xee; 3]);
+/// ```
+pub fn c256_to_rgb(idx: u8) -> Rgb {
+    const BASE16: [u32; 16] = [
+        0x000000, 0xcd0000, 0x00cd00, 0xcdcd00,
+        0x0000ee, 0xcd00cd, 0x00cdcd, 0xe5e5e5,
+        0x7f7f7f, 0xff0000, 0x00ff00, 0xffff00,
+        0x5c5cff, 0xff00ff, 0x00ffff, 0xffffff,
+    ];
+    match idx {
+        0..=15 => num_to_rgb(BASE16[idx as usize]),
+        16..=231 => {
+            let idx = idx - 16;
+            let level = |n: u8| if n == 0 { 0 } else { n * 40 + 55 };
+            [level(idx / 36), level(idx / 6 % 6), level(idx % 6)]
+        },
+        _ => [(idx - 232) * 10 + 8; 3],
+    }
+}
+
+/// 获取颜色的 RGB 值, `Color::None` 无法获取
+pub fn color_to_rgb(color: Color) -> Option<Rgb> {
+    match color {
+        Color::Rgb(rgb) => Some(rgb),
+        Color::C256(idx) => Some(c256_to_rgb(idx)),
+        Color::None => None,
+    }
+}
+
+/// HSL 转换为 RGB, 色相单位为度, 饱和度与亮度范围为 0 到 1
+/// # Examples
+/// ```
+/// use timg::hsl_to_rgb;
+/// assert_eq!(hsl_to_rgb(0.0, 1.0, 0.5), [255, 0, 0]);
+/// assert_eq!(hsl_to_rgb(120.0, 1.0, 0.25), [0, 128, 0]);
+/// assert_eq!(hsl_to_rgb(240.0, 0.0, 1.0), [255, 255, 255]);
+/// ```
+pub fn hsl_to_rgb(hue: Float, saturation: Float, lightness: Float) -> Rgb {
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    [r, g, b].map(|n| ((n + m) * 255.0).round().clamp(0.0, 255.0) as u8)
+}
+
+/// 解析函数形式的颜色参数, 如 `rgb(1, 2, 3)` 中的 `1, 2, 3`
+/// 返回的百分数已转换到 0 到 1, 并标记其是否为百分数
+fn parse_color_args(args: &str) -> Option<[(Float, bool); 3]> {
+    let args = args.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| ! s.is_empty())
+        .collect::<Vec<_>>();
+    if args.len() != 3 {
+        return None;
+    }
+    let mut res = [(0.0, false); 3];
+    for (i, arg) in args.into_iter().enumerate() {
+        res[i] = if let Some(num) = arg.strip_suffix('%') {
+            (num.parse::<Float>().ok()? / 100.0, true)
+        } else {
+            (arg.strip_suffix("deg").unwrap_or(arg).parse().ok()?, false)
+        };
+    }
+    Some(res)
+}
+
+/// Parse a color.
+///
+/// Supported formats:
+/// - `#rgb`, `#rrggbb`, or `rrggbb` without `#`
+/// - `rgb(r, g, b)`, components are `0..=255` or percentages
+/// - `hsl(h, s%, l%)`
+/// - CSS color names, and X11 `grayN` `greyN` (N in `0..=100`)
+/// - `none` or `terminal`, the terminal default color
+/// - 256-color index `0..=255`, or `c0..=c255`
+///
+/// # Examples
+/// ```
+/// use term_lattice::Color;
+/// use timg::parse_color;
+/// assert_eq!(parse_color("#f80"), Ok(Color::Rgb([0xff, 0x88, 0x00])));
+/// assert_eq!(parse_color("#Ff8000"), Ok(Color::Rgb([0xff, 0x80, 0x00])));
+/// assert_eq!(parse_color("888888"), Ok(Color::Rgb([0x88; 3])));
+/// assert_eq!(parse_color("rgb(100, 0, 50%)"), Ok(Color::Rgb([100, 0, 128])));
+/// assert_eq!(parse_color("hsl(120, 100%, 25%)"), Ok(Color::Rgb([0, 128, 0])));
+/// assert_eq!(parse_color("RebeccaPurple"), Ok(Color::Rgb([0x66, 0x33, 0x99])));
+/// assert_eq!(parse_color("gray50"), Ok(Color::Rgb([0x7f; 3])));
+/// assert_eq!(parse_color("terminal"), Ok(Color::None));
+/// assert_eq!(parse_color("84"), Ok(Color::C256(84)));
+/// assert_eq!(parse_color("C084"), Ok(Color::C256(84)));
+/// assert!(parse_color("#12345").is_err());
+/// assert!(parse_color("256").is_err());
+/// assert!(parse_color("nocolor").is_err());
+/// ```
+pub fn parse_color(s: &str) -> Result<Color, ParseColorError> {
+    let s = s.trim();
+    let lower = s.to_ascii_lowercase();
+    let hex = |digits: &str| -> Result<Color, ParseColorError> {
+        let num = base16_to_unum(digits)
+            .filter(|_| digits.is_ascii())
+            .ok_or_else(|| ParseColorError::Hex(s.to_string()))?;
+        match digits.len() {
+            // #rgb 的每一位重复一次, 如 #f80 => #ff8800
+            3 => Ok(Color::Rgb([num >> 8, num >> 4, num]
+                               .map(|n| (n & 0xf) as u8 * 0x11))),
+            6 => Ok(Color::Rgb(num_to_rgb(num))),
+            _ => Err(ParseColorError::Hex(s.to_string())),
+        }
+    };
+    let index = |digits: &str| -> Result<Color, ParseColorError> {
+        digits.parse::<u8>()
+            .map(Color::C256)
+            .map_err(|_| ParseColorError::Index(s.to_string()))
+    };
+    let func = |args: &str| -> Result<[(Float, bool); 3], ParseColorError> {
+        args.strip_prefix('(')
+            .and_then(|args| args.strip_suffix(')'))
+            .and_then(parse_color_args)
+            .ok_or_else(|| ParseColorError::Function(s.to_string()))
+    };
+    match lower.as_str() {
+        "" => Err(ParseColorError::Empty),
+        "none" | "terminal" => Ok(Color::None),
+        x if x.starts_with('#') => hex(&x[1..]),
+        x if x.len() == 6 && x.chars().all(|c| c.is_ascii_hexdigit())
+            => hex(x),
+        x if x.chars().all(|c| c.is_ascii_digit()) => index(x),
+        x if x.starts_with('c')
+            && x.len() > 1
+            && x[1..].chars().all(|c| c.is_ascii_digit()) => index(&x[1..]),
+        x if x.starts_with("rgb") => {
+            let args = func(x["rgb".len()..].trim_start())?;
+            Ok(Color::Rgb(args.map(|(n, percent)| {
+                if percent { n * 255.0 } else { n }
+                    .round().clamp(0.0, 255.0) as u8
+            })))
+        },
+        x if x.starts_with("hsl") => {
+            let [(h, _), s, l] = func(x["hsl".len()..].trim_start())?;
+            // 饱和度与亮度未写 `%` 时也按百分数处理
+            let [s, l] = [s, l].map(|(n, percent)| {
+                if percent { n } else { n / 100.0 }
+            });
+            Ok(Color::Rgb(hsl_to_rgb(h, s, l)))
+        },
+        x => {
+            if let Some(&(_, num)) = COLOR_NAMES.iter()
+                .find(|(name, _)| *name == x) {
+                return Ok(Color::Rgb(num_to_rgb(num)));
+            }
+            // X11 的灰度颜色, 如 gray50
+            x.strip_prefix("gray").or_else(|| x.strip_prefix("grey"))
+                .and_then(|n| n.parse::<u8>().ok())
+                .filter(|&n| n <= 100)
+                .map(|n| Color::Rgb([(n as Float * 2.55).round() as u8; 3]))
+                .ok_or_else(|| ParseColorError::Name(s.to_string()))
+        },
+    }
+}
+
+/// 解析 X11 的 `rgb:r/g/b` 颜色, 每个分量为 1 到 4 位十六进制数,
+/// 终端对 OSC 11 等查询的回复使用这种格式
+/// # Examples
+/// ```
+/// use timg::parse_x_color;
+/// assert_eq!(parse_x_color("rgb:ffff/8080/0000"), Some([0xff, 0x80, 0x00]));
+/// assert_eq!(parse_x_color("rgb:f/8/0"), Some([0xff, 0x88, 0x00]));
+/// assert_eq!(parse_x_color("rgb:1e1e/1e/2"), Some([0x1e, 0x1e, 0x22]));
+/// assert_eq!(parse_x_color("rgb:12345/0/0"), None);
+/// assert_eq!(parse_x_color("#000"), None);
+/// ```
+pub fn parse_x_color(s: &str) -> Option<Rgb> {
+    let nums = s.trim().strip_prefix("rgb:")?.split('/')
+        .map(|n| {
+            if n.is_empty() || n.len() > 4 {
+                return None;
+            }
+            let max = (1_u32 << (n.len() * 4)) - 1;
+            let num = base16_to_unum(n)?;
+            Some((num as Float * 255.0 / max as Float).round() as u8)
+        })
+        .collect::<Option<Vec<_>>>()?;
+    match nums[..] {
+        [r, g, b] => Some([r, g, b]),
+        _ => None,
+    }
+}
+
+/// 以不在括号中的逗号分割颜色列表
+/// # Examples
+/// ```
+/// use timg::split_colors;
+/// assert_eq!(split_colors("red, rgb(1,2,3),#fff"),
+///            vec!["red", "rgb(1,2,3)", "#fff"]);
+/// ```
+pub fn split_colors(s: &str) -> Vec<&str> {
+    let mut res = Vec::new();
+    let (mut depth, mut start) = (0_usize, 0);
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                res.push(s[start..i].trim());
+                start = i + 1;
+            },
+            _ => (),
+        }
+    }
+    res.push(s[start..].trim());
+    res
+}
+
+/// 解析以逗号分割的多个颜色
+pub fn parse_colors(s: &str) -> Result<Vec<Color>, ParseColorError> {
+    split_colors(s).into_iter().map(parse_color).collect()
+}
+
+#[test]
+fn parse_color_test() {
+    assert_eq!(parse_color(" white "), Ok(Color::Rgb([255; 3])));
+    assert_eq!(parse_color("c255"), Ok(Color::C256(255)));
+    assert_eq!(parse_color("rgb 0 128 255"), Err(ParseColorError::Function(
+                "rgb 0 128 255".to_string())));
+    assert_eq!(parse_color("rgb(0 128 255)"), Ok(Color::Rgb([0, 128, 255])));
+    assert_eq!(parse_color("hsl(240deg 100% 50%)"), Ok(Color::Rgb([0, 0, 255])));
+    assert_eq!(parse_color("grey100"), Ok(Color::Rgb([255; 3])));
+    assert_eq!(parse_color("gray0"), Ok(Color::Rgb([0; 3])));
+    assert!(parse_color("gray101").is_err());
+    assert_eq!(parse_colors("none,#000,c1"),
+               Ok(vec![Color::None, Color::Rgb([0; 3]), Color::C256(1)]));
+    assert_eq!(parse_colors("1,,2"), Err(ParseColorError::Empty));
+    for (name, num) in COLOR_NAMES {
+        assert_eq!(parse_color(name), Ok(Color::Rgb(num_to_rgb(*num))));
+    }
+}
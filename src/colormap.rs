@@ -0,0 +1,183 @@
+use std::ops::DerefMut;
+
+use image::ImageBuffer;
+use term_lattice::types::Rgb;
+
+use super::*;
+
+/// 等间距的色标, 取自 matplotlib
+const VIRIDIS: [u32; 10] = [
+    0x440154, 0x482878, 0x3e4a89, 0x31688e, 0x26828e,
+    0x1f9e89, 0x35b779, 0x6dcd59, 0xb4de2c, 0xfde725,
+];
+const MAGMA: [u32; 10] = [
+    0x000004, 0x180f3e, 0x451077, 0x721f81, 0x9f2f7f,
+    0xcd4071, 0xf1605d, 0xfd9567, 0xfec98d, 0xfcfdbf,
+];
+const INFERNO: [u32; 10] = [
+    0x000004, 0x1b0c42, 0x4b0c6b, 0x781c6d, 0xa52c60,
+    0xcf4446, 0xed6925, 0xfb9a06, 0xf7d03c, 0xfcffa4,
+];
+
+/// 在色标之间线性插值
+fn interpolate(stops: &[u32], t: Float) -> Rgb {
+    let pos = t * (stops.len() - 1) as Float;
+    let idx = (pos as usize).min(stops.len() - 2);
+    let weight = pos - idx as Float;
+    let [a, b] = [stops[idx], stops[idx + 1]].map(num_to_rgb);
+    let mut res = [0; 3];
+    for i in 0..3 {
+        res[i] = (a[i] as Float * (1.0 - weight) + b[i] as Float * weight)
+            .round() as u8;
+    }
+    res
+}
+
+/// False-color colormaps for grayscale data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colormap {
+    Viridis,
+    Magma,
+    Inferno,
+    Jet,
+    Turbo,
+}
+impl Colormap {
+    pub const ALL: [Self; 5] = [
+        Self::Viridis, Self::Magma, Self::Inferno, Self::Jet, Self::Turbo,
+    ];
+    /// 在 `None` 与 [`Self::ALL`] 之间循环
+    /// # Examples
+    /// ```
+    /// use timg::Colormap;
+    /// assert_eq!(Colormap::cycle(None), Some(Colormap::Viridis));
+    /// assert_eq!(Colormap::cycle(Some(Colormap::Viridis)), Some(Colormap::Magma));
+    /// assert_eq!(Colormap::cycle(Some(Colormap::Turbo)), None);
+    /// ```
+    pub fn cycle(colormap: Option<Self>) -> Option<Self> {
+        match colormap {
+            None => Some(Self::ALL[0]),
+            Some(x) => {
+                let idx = Self::ALL.iter().position(|&y| y == x).unwrap();
+                Self::ALL.get(idx + 1).copied()
+            },
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Viridis => "viridis",
+            Self::Magma => "magma",
+            Self::Inferno => "inferno",
+            Self::Jet => "jet",
+            Self::Turbo => "turbo",
+        }
+    }
+    /// 获取 0 到 1 处的颜色
+    /// # Examples
+    /// ```
+    /// use timg::Colormap;
+    /// assert_eq!(Colormap::Viridis.color_at(0.0), [0x44, 0x01, 0x54]);
+    /// assert_eq!(Colormap::Viridis.color_at(1.0), [0xfd, 0xe7, 0x25]);
+    /// assert_eq!(Colormap::Jet.color_at(0.5), [128, 255, 128]);
+    /// ```
+    pub fn color_at(&self, t: Float) -> Rgb {
+        let t = t.clamp(0.0, 1.0);
+        let to_u8 = |x: Float| (x.clamp(0.0, 1.0) * 255.0).round() as u8;
+        match self {
+            Self::Viridis => interpolate(&VIRIDIS, t),
+            Self::Magma => interpolate(&MAGMA, t),
+            Self::Inferno => interpolate(&INFERNO, t),
+            Self::Jet => [3.0, 2.0, 1.0]
+                .map(|x| to_u8(1.5 - (4.0 * t - x).abs())),
+            Self::Turbo => {
+                // Google 的多项式近似
+                let poly = |c: [Float; 6]| to_u8(
+                    c[0] + t * (c[1] + t * (c[2] + t * (c[3] + t * (c[4] + t * c[5])))));
+                [
+                    poly([0.13572138, 4.61539260, -42.66032258,
+                          132.13108234, -152.94239396, 59.28637943]),
+                    poly([0.09140261, 2.19418839, 4.84296658,
+                          -14.18503333, 4.27729857, 2.82956604]),
+                    poly([0.10667330, 12.64194608, -60.58204836,
+                          110.36276771, -89.90310912, 27.34824973]),
+                ]
+            },
+        }
+    }
+    /// 将 `window` 范围内的值映射到整个色图的查找表
+    fn table(&self, window: [u8; 2]) -> [Rgb; 256] {
+        let [min, max] = window.map(|x| x as Float);
+        let mut table = [[0; 3]; 256];
+        for (i, x) in table.iter_mut().enumerate() {
+            *x = self.color_at((i as Float - min) / (max - min).max(1.0));
+        }
+        table
+    }
+    /// 按亮度映射图片, 透明度不变
+    pub fn apply<C>(&self, img: &mut ImageBuffer<image::Rgba<u8>, C>, window: [u8; 2])
+        where C: DerefMut<Target = [u8]>
+    {
+        let table = self.table(window);
+        for pixel in img.pixels_mut() {
+            let [r, g, b, a] = pixel.0;
+            let [r, g, b] = table[luma([r, g, b]) as usize];
+            pixel.0 = [r, g, b, a];
+        }
+    }
+    /// 用于状态行的图例, 宽度为 `width` 个字符
+    pub fn legend(&self, width: usize, window: [u8; 2]) -> String {
+        let mut res = format!("{}", window[0]);
+        res.push_str("\x1b[27m"); // 关闭反色
+        for i in 0..width {
+            let [r, g, b] = self.color_at(
+                (i as Float + 0.5) / width.max(1) as Float);
+            res.push_str(&format!("\x1b[48;2;{};{};{}m ", r, g, b));
+        }
+        res.push_str(&format!("\x1b[49;7m{}", window[1]));
+        res
+    }
+}
+
+/// 调整色图的窗口, 保持最小值小于最大值
+/// # Examples
+/// ```
+/// use timg::step_window;
+/// assert_eq!(step_window([0, 255], 0, 16), [16, 255]);
+/// assert_eq!(step_window([0, 255], 1, 16), [0, 255]);
+/// assert_eq!(step_window([100, 110], 1, -16), [100, 101]);
+/// ```
+pub fn step_window(window: [u8; 2], idx: usize, step: i16) -> [u8; 2] {
+    let mut res = window;
+    let value = window[idx] as i16 + step;
+    res[idx] = if idx == 0 {
+        value.clamp(0, window[1] as i16 - 1)
+    } else {
+        value.clamp(window[0] as i16 + 1, 255)
+    } as u8;
+    res
+}
+
+#[test]
+fn colormap_test() {
+    use image::{Rgba, RgbaImage};
+
+    for colormap in Colormap::ALL {
+        // 除 jet 与 turbo 以外的色图亮度是单调递增的
+        if matches!(colormap, Colormap::Jet | Colormap::Turbo) {
+            continue;
+        }
+        let lumas: Vec<u8> = (0..=16)
+            .map(|i| luma(colormap.color_at(i as Float / 16.0)))
+            .collect();
+        assert!(lumas.windows(2).all(|x| x[0] <= x[1]), "{:?}", colormap);
+    }
+    assert_eq!(Colormap::Turbo.color_at(0.0), [35, 23, 27]);
+
+    let mut img = RgbaImage::from_fn(3, 1, |x, _| {
+        let v = [50, 100, 150][x as usize];
+        Rgba([v, v, v, 200])
+    });
+    Colormap::Viridis.apply(&mut img, [100, 150]);
+    assert_eq!(img.get_pixel(0, 0).0, [0x44, 0x01, 0x54, 200]);
+    assert_eq!(img.get_pixel(2, 0).0, [0xfd, 0xe7, 0x25, 200]);
+}
@@ -0,0 +1,231 @@
+use std::ops::DerefMut;
+
+use image::{ImageBuffer, RgbaImage};
+
+use super::*;
+
+/// 自动色阶时两端各裁去的像素比例
+pub const LEVELS_CLIP: Float = 0.005;
+/// CLAHE 在每个方向上划分的块数
+pub const CLAHE_TILES: SizeType = 8;
+/// CLAHE 的对比度限制, 为直方图平均高度的倍数
+pub const CLAHE_CLIP_LIMIT: Float = 3.0;
+
+/// BT.601 亮度
+/// # Examples
+/// ```
+/// use timg::luma;
+/// assert_eq!(luma([255, 255, 255]), 255);
+/// assert_eq!(luma([255, 0, 0]), 76);
+/// ```
+pub fn luma(rgb: [u8; 3]) -> u8 {
+    let [r, g, b] = rgb.map(|x| x as u32);
+    ((r * 299 + g * 587 + b * 114 + 500) / 1000) as u8
+}
+
+/// 图片增强模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnhanceMode {
+    Off,
+    /// 拉伸每个通道的直方图
+    Levels,
+    /// 限制对比度的自适应直方图均衡化
+    Clahe,
+}
+impl EnhanceMode {
+    /// # Examples
+    /// ```
+    /// use timg::EnhanceMode;
+    /// assert_eq!(EnhanceMode::Off.next(), EnhanceMode::Levels);
+    /// assert_eq!(EnhanceMode::Clahe.next(), EnhanceMode::Off);
+    /// ```
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Levels,
+            Self::Levels => Self::Clahe,
+            Self::Clahe => Self::Off,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Levels => "Levels",
+            Self::Clahe => "CLAHE",
+        }
+    }
+}
+
+/// 统计不透明像素各通道的直方图
+fn channel_histograms(img: &RgbaImage) -> [[u64; 256]; 3] {
+    let mut res = [[0; 256]; 3];
+    for pixel in img.pixels().filter(|p| p.0[3] != 0) {
+        for (hist, value) in res.iter_mut().zip(pixel.0) {
+            hist[value as usize] += 1;
+        }
+    }
+    res
+}
+
+/// Auto-levels, stretch each channel's histogram to the full range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Levels {
+    tables: [[u8; 256]; 3],
+}
+impl Levels {
+    /// 由整个图片的直方图计算
+    pub fn new(img: &RgbaImage) -> Self {
+        let mut tables = [[0; 256]; 3];
+        for (table, hist) in tables.iter_mut().zip(channel_histograms(img)) {
+            let total: u64 = hist.iter().sum();
+            let clip = (total as Float * LEVELS_CLIP) as u64;
+            // 找到累计数量超过 clip 的位置
+            let find = |mut iter: Box<dyn Iterator<Item = usize>>| {
+                let mut sum = 0;
+                iter.find(|&i| {
+                    sum += hist[i];
+                    sum > clip
+                })
+            };
+            let low = find(Box::new(0..256)).unwrap_or(0);
+            let high = find(Box::new((0..256).rev())).unwrap_or(255);
+            for (i, x) in table.iter_mut().enumerate() {
+                *x = if high <= low {
+                    i as u8
+                } else {
+                    let value = (i as Float - low as Float) * 255.0
+                        / (high - low) as Float;
+                    value.round().clamp(0.0, 255.0) as u8
+                };
+            }
+        }
+        Self { tables }
+    }
+    pub fn apply<C>(&self, img: &mut ImageBuffer<image::Rgba<u8>, C>)
+        where C: DerefMut<Target = [u8]>
+    {
+        for pixel in img.pixels_mut() {
+            for (value, table) in pixel.0.iter_mut().zip(&self.tables) {
+                *value = table[*value as usize];
+            }
+        }
+    }
+}
+
+/// Contrast limited adaptive histogram equalization on luma.
+///
+/// The mapping of every tile is computed once in source image
+/// coordinates, so frames of any viewport can be mapped by bilinear
+/// interpolating the tiles around the source position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clahe {
+    tiles: Position,
+    tile_size: [Float; 2],
+    tables: Vec<[u8; 256]>,
+}
+impl Clahe {
+    pub fn new(img: &RgbaImage, tiles: SizeType, clip_limit: Float) -> Self {
+        let (width, height) = img.dimensions();
+        let tiles = Position::new(tiles.clamp(1, width.max(1)),
+                                  tiles.clamp(1, height.max(1)));
+        let tile_size = [width as Float / tiles.x as Float,
+                         height as Float / tiles.y as Float];
+        let mut hists = vec![[0_u64; 256]; (tiles.x * tiles.y) as usize];
+        for (x, y, pixel) in img.enumerate_pixels() {
+            let [r, g, b, _] = pixel.0;
+            let tx = ((x as Float / tile_size[0]) as SizeType).min(tiles.x - 1);
+            let ty = ((y as Float / tile_size[1]) as SizeType).min(tiles.y - 1);
+            hists[(ty * tiles.x + tx) as usize][luma([r, g, b]) as usize] += 1;
+        }
+        let tables = hists.into_iter().map(|mut hist| {
+            let total: u64 = hist.iter().sum();
+            // 裁去超过限制的部分, 并平均分配到每一级
+            let limit = ((total as Float / 256.0 * clip_limit) as u64).max(1);
+            let mut excess = 0;
+            for count in hist.iter_mut() {
+                if *count > limit {
+                    excess += *count - limit;
+                    *count = limit;
+                }
+            }
+            let (add, rest) = (excess / 256, (excess % 256) as usize);
+            for (i, count) in hist.iter_mut().enumerate() {
+                *count += add + (i < rest) as u64;
+            }
+            let mut table = [0; 256];
+            let mut sum = 0;
+            for (x, count) in table.iter_mut().zip(hist) {
+                sum += count;
+                *x = (sum as Float * 255.0 / total.max(1) as Float).round() as u8;
+            }
+            table
+        }).collect();
+        Self { tiles, tile_size, tables }
+    }
+    /// 获取源图片位置处亮度的映射结果
+    pub fn map(&self, x: Float, y: Float, luma: u8) -> u8 {
+        let max = [(self.tiles.x - 1) as Float, (self.tiles.y - 1) as Float];
+        // 相对于块中心的坐标
+        let fx = (x / self.tile_size[0] - 0.5).clamp(0.0, max[0]);
+        let fy = (y / self.tile_size[1] - 0.5).clamp(0.0, max[1]);
+        let (x0, y0) = (fx.floor(), fy.floor());
+        let (x1, y1) = ((x0 + 1.0).min(max[0]), (y0 + 1.0).min(max[1]));
+        let (wx, wy) = (fx - x0, fy - y0);
+        let get = |x: Float, y: Float| {
+            let idx = y as usize * self.tiles.x as usize + x as usize;
+            self.tables[idx][luma as usize] as Float
+        };
+        let top = get(x0, y0) * (1.0 - wx) + get(x1, y0) * wx;
+        let bottom = get(x0, y1) * (1.0 - wx) + get(x1, y1) * wx;
+        (top * (1.0 - wy) + bottom * wy).round() as u8
+    }
+    /// 映射一帧图片, `to_source` 将帧中的位置转换到源图片中的位置
+    pub fn apply<C, F>(&self, img: &mut ImageBuffer<image::Rgba<u8>, C>, to_source: F)
+        where C: DerefMut<Target = [u8]>,
+              F: Fn(SizeType, SizeType) -> [Float; 2]
+    {
+        for (x, y, pixel) in img.enumerate_pixels_mut() {
+            let [r, g, b, _] = &mut pixel.0;
+            let old = luma([*r, *g, *b]);
+            let [sx, sy] = to_source(x, y);
+            // 保持色度, 仅改变亮度
+            let delta = self.map(sx, sy, old) as i16 - old as i16;
+            for value in [r, g, b] {
+                *value = (*value as i16 + delta).clamp(0, 255) as u8;
+            }
+        }
+    }
+}
+
+#[test]
+fn levels_test() {
+    use image::Rgba;
+
+    let mut img = RgbaImage::from_fn(100, 1, |x, _| {
+        let v = 100 + (x / 2) as u8;
+        Rgba([v, v / 2, 200, 255])
+    });
+    let levels = Levels::new(&img);
+    levels.apply(&mut img);
+    assert_eq!(img.get_pixel(0, 0).0, [0, 0, 200, 255]);
+    assert_eq!(img.get_pixel(99, 0).0, [255, 255, 200, 255]);
+}
+
+#[test]
+fn clahe_test() {
+    use image::Rgba;
+
+    // 左半部分暗, 右半部分亮, 且各自对比度很低
+    let src = RgbaImage::from_fn(64, 64, |x, y| {
+        let v = if x < 32 { 20 } else { 220 } + ((x + y) % 4) as u8;
+        Rgba([v, v, v, 255])
+    });
+    let clahe = Clahe::new(&src, 4, 40.0);
+    let mut img = src.clone();
+    clahe.apply(&mut img, |x, y| [x as Float + 0.5, y as Float + 0.5]);
+    let contrast = |img: &RgbaImage, x0: u32| {
+        let values = (x0..x0 + 8).map(|x| img.get_pixel(x, 8).0[0]);
+        values.clone().max().unwrap() - values.min().unwrap()
+    };
+    assert!(contrast(&img, 4) > contrast(&src, 4) * 4);
+    assert!(contrast(&img, 52) > contrast(&src, 52) * 4);
+}
@@ -0,0 +1,147 @@
+use image::{
+    Rgba,
+    RgbImage,
+};
+use term_lattice::types::Rgb;
+
+use super::*;
+
+/// 直方图面板的默认背景色
+pub const DEFAULT_HIST_BACKGROUND: Rgb = [0x20; 3];
+
+/// 直方图的通道
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistChannel {
+    Red,
+    Green,
+    Blue,
+    Luma,
+}
+impl HistChannel {
+    pub const ALL: [Self; 4] = [Self::Red, Self::Green, Self::Blue, Self::Luma];
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Self::Red => "R",
+            Self::Green => "G",
+            Self::Blue => "B",
+            Self::Luma => "Y",
+        }
+    }
+}
+
+/// RGB and luma histograms of the non-transparent pixels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    bins: [[u64; 256]; 4],
+    count: u64,
+}
+impl Histogram {
+    pub fn new<'a, I>(pixels: I) -> Self
+        where I: IntoIterator<Item = &'a Rgba<u8>>
+    {
+        let mut bins = [[0; 256]; 4];
+        let mut count = 0;
+        for &Rgba([r, g, b, a]) in pixels {
+            if a == 0 {
+                continue;
+            }
+            for (bin, value) in bins.iter_mut().zip([r, g, b, luma([r, g, b])]) {
+                bin[value as usize] += 1;
+            }
+            count += 1;
+        }
+        Self { bins, count }
+    }
+    /// 统计的像素数量
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+    pub fn bins(&self, channel: HistChannel) -> &[u64; 256] {
+        &self.bins[channel as usize]
+    }
+    /// 通道的最小值, 最大值与平均值
+    /// # Examples
+    /// ```
+    /// # use image::Rgba;
+    /// use timg::{Histogram, HistChannel};
+    /// let pixels = [Rgba([10, 0, 0, 255]), Rgba([30, 0, 0, 255]),
+    ///               Rgba([255, 0, 0, 0])];
+    /// let hist = Histogram::new(&pixels);
+    /// assert_eq!(hist.stats(HistChannel::Red), Some((10, 30, 20.0)));
+    /// assert_eq!(Histogram::new(&[]).stats(HistChannel::Red), None);
+    /// ```
+    pub fn stats(&self, channel: HistChannel) -> Option<(u8, u8, Float)> {
+        let bins = self.bins(channel);
+        let min = bins.iter().position(|&x| x != 0)?;
+        let max = bins.iter().rposition(|&x| x != 0)?;
+        let sum: u64 = bins.iter().enumerate()
+            .map(|(i, &x)| i as u64 * x)
+            .sum();
+        Some((min as u8, max as u8, sum as Float / self.count as Float))
+    }
+    /// 格式化所有通道的统计信息
+    pub fn fmt_stats(&self) -> String {
+        HistChannel::ALL.iter()
+            .filter_map(|&ch| {
+                let (min, max, mean) = self.stats(ch)?;
+                Some(format!("{}:{}-{}~{:.1}", ch.short_name(), min, max, mean))
+            })
+            .collect::<Vec<_>>().join(" ")
+    }
+    /// Draw the histograms into a panel image.
+    ///
+    /// RGB bars are mixed additively, so the overlapping parts are shown
+    /// as the mixed color, the luma histogram is drawn as a gray outline.
+    pub fn draw(&self, width: SizeType, height: SizeType,
+                back_ground: Rgb) -> RgbImage {
+        let width = width.max(1);
+        // 每一列所包含的级的平均数量
+        let columns: Vec<[Float; 4]> = (0..width).map(|x| {
+            let start = (x * 256 / width) as usize;
+            let end = (((x + 1) * 256 / width) as usize).max(start + 1);
+            let mut res = [0.0; 4];
+            for (value, bins) in res.iter_mut().zip(&self.bins) {
+                *value = bins[start..end].iter().sum::<u64>() as Float
+                    / (end - start) as Float;
+            }
+            res
+        }).collect();
+        let peak = columns.iter()
+            .flat_map(|x| x.iter().copied())
+            .fold(1.0, Float::max);
+        let bar = |value: Float| (value / peak * height as Float).round() as SizeType;
+        RgbImage::from_fn(width, height, |x, y| {
+            let level = height - y; // 从底部开始的高度
+            let [r, g, b, l] = columns[x as usize].map(bar);
+            if level == l {
+                return image::Rgb([0xcc; 3]);
+            }
+            let mut color = back_ground;
+            for (i, bar) in [r, g, b].into_iter().enumerate() {
+                if level <= bar {
+                    color[i] = color[i].saturating_add(0xc0);
+                }
+            }
+            image::Rgb(color)
+        })
+    }
+}
+
+#[test]
+fn histogram_test() {
+    let pixels: Vec<_> = (0..=255).map(|x| Rgba([x, 0, 255, 255])).collect();
+    let hist = Histogram::new(&pixels);
+    assert_eq!(hist.count(), 256);
+    assert_eq!(hist.stats(HistChannel::Green), Some((0, 0, 0.0)));
+    assert_eq!(hist.stats(HistChannel::Red), Some((0, 255, 127.5)));
+    assert_eq!(hist.bins(HistChannel::Blue)[255], 256);
+
+    let panel = hist.draw(64, 8, [0; 3]);
+    assert_eq!(panel.dimensions(), (64, 8));
+    // 绿色仅在第一列, 蓝色仅在最后一列
+    assert_eq!(panel.get_pixel(0, 0).0, [0, 0xc0, 0]);
+    assert_eq!(panel.get_pixel(63, 0).0, [0, 0, 0xc0]);
+    assert_eq!(panel.get_pixel(63, 7).0[1], 0);
+    // 红色每列的数量很少, 只在底部
+    assert_eq!(panel.get_pixel(30, 3).0[0], 0);
+}
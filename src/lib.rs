@@ -144,6 +144,77 @@ mod position {
 }
 pub use position::*;
 
+mod ascii;
+pub use ascii::*;
+
+mod background;
+pub use background::*;
+
+mod color;
+pub use color::*;
+
+mod linear;
+pub use linear::*;
+
+mod resample;
+pub use resample::*;
+
+mod adjust;
+pub use adjust::*;
+
+mod enhance;
+pub use enhance::*;
+
+mod histogram;
+pub use histogram::*;
+
+mod channel;
+pub use channel::*;
+
+mod colormap;
+pub use colormap::*;
+
+mod tonemap;
+pub use tonemap::*;
+
+mod raw;
+pub use raw::*;
+
+mod npy;
+pub use npy::*;
+
+mod orientation;
+pub use orientation::*;
+
+mod stream;
+pub use stream::*;
+
+mod watch;
+pub use watch::*;
+
+mod source;
+pub use source::*;
+
+mod slideshow;
+pub use slideshow::*;
+
+mod memo;
+pub use memo::*;
+
+mod mipmap;
+pub use mipmap::*;
+
+mod scroll;
+pub use scroll::*;
+
+mod bandwidth;
+pub use bandwidth::*;
+
+#[cfg(unix)]
+mod term;
+#[cfg(unix)]
+pub use term::*;
+
 /// 获取要将图片大小缩到刚好放进终端大小时, 终端大小须乘的比例
 /// 终端大小 * 比例 得到刚好包括整个图片的大小
 pub fn get_scale(term_size: Position, img_size: Position) -> Float {
@@ -157,6 +228,50 @@ pub fn get_scale(term_size: Position, img_size: Position) -> Float {
         ih / th
     }
 }
+
+/// 同 [`get_scale`], 但虚拟像素的高是宽的 `pixel_aspect` 倍
+/// # Examples
+/// ```
+/// use timg::{get_scale, get_scale_aspect, Position};
+/// let term_size = Position::new(80, 60);
+/// let img_size = Position::new(240, 740);
+/// assert_eq!(get_scale_aspect(term_size, img_size, 1.0),
+///            get_scale(term_size, img_size));
+/// assert_eq!(get_scale_aspect(term_size, Position::new(80, 120), 2.0), 1.0);
+/// ```
+pub fn get_scale_aspect(term_size: Position, img_size: Position,
+                        pixel_aspect: Float) -> Float {
+    let (tw, th, iw, ih)
+        = asf!(term_size.x, term_size.y, img_size.x, img_size.y);
+    (iw / tw).max(ih / (th * pixel_aspect))
+}
+
+/// 获取缩放比例下, 终端所能显示的图片区域大小
+/// # Examples
+/// ```
+/// use timg::{get_view_size, Position};
+/// let term_size = Position::new(80, 60);
+/// assert_eq!(get_view_size(term_size, 2.0, 1.0), Position::new(160, 120));
+/// assert_eq!(get_view_size(term_size, 2.0, 1.5), Position::new(160, 180));
+/// ```
+pub fn get_view_size(term_size: Position, scale: Float,
+                     pixel_aspect: Float) -> Position {
+    let (tw, th) = asf!(term_size.x, term_size.y);
+    Position::new((tw * scale) as SizeType,
+                  (th * scale * pixel_aspect) as SizeType)
+}
+
+/// 由字符单元的像素大小得到半块虚拟像素的高宽比
+/// # Examples
+/// ```
+/// use timg::{cell_pixel_aspect, Position};
+/// assert_eq!(cell_pixel_aspect(Position::new(8, 16)), 1.0);
+/// assert_eq!(cell_pixel_aspect(Position::new(10, 24)), 1.2);
+/// ```
+pub fn cell_pixel_aspect(cell_size: Position) -> Float {
+    let (w, h) = asf!(cell_size.x, cell_size.y);
+    h / 2.0 / w
+}
 #[test]
 fn get_scale_test() {
     let term_size = Position::new(80, 60);
@@ -254,3 +369,34 @@ pub fn num_to_rgb(num: u32) -> [u8; 3] {
      ((num >> 8) & 0xff) as u8,
      (num & 0xff) as u8]
 }
+
+use term_lattice::types::Rgb;
+
+pub type Rgba = [u8; 4];
+
+/// RGBA color to RGB color
+/// # Examples
+/// ```
+/// use timg::rgba_to_rgb;
+/// assert_eq!(rgba_to_rgb([100, 149, 237, 200], [255; 3]), [133, 171, 240]);
+/// ```
+pub fn rgba_to_rgb(foreground: Rgba, background: Rgb) -> Rgb {
+    macro_rules! int {
+        ( $x:expr ) => {
+            ($x) as u8
+        };
+    }
+    macro_rules! float {
+        ( $x:expr ) => {
+            ($x) as Float
+        };
+    }
+    let [r1, g1, b1, a1] = foreground;
+    let [r2, g2, b2] = background;
+    let alpha = a1 as Float / 255.0;
+    let [r, g, b]: [u8; 3];
+    r = int!(float!(r1) * alpha + float!(r2) * (1.0 - alpha));
+    g = int!(float!(g1) * alpha + float!(g2) * (1.0 - alpha));
+    b = int!(float!(b1) * alpha + float!(b2) * (1.0 - alpha));
+    [r, g, b]
+}
@@ -0,0 +1,311 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    OnceLock,
+};
+
+use image::{
+    imageops::FilterType,
+    DynamicImage,
+    GenericImageView,
+    Rgba32FImage,
+    RgbaImage,
+};
+use term_lattice::types::Rgb;
+
+use super::*;
+
+/// sRGB 编码值转换为线性光强
+/// # Examples
+/// ```
+/// use timg::srgb_to_linear;
+/// assert_eq!(srgb_to_linear(0.0), 0.0);
+/// assert_eq!(srgb_to_linear(1.0), 1.0);
+/// assert!((srgb_to_linear(0.5) - 0.214_041).abs() < 1e-6);
+/// ```
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// 线性光强转换为 sRGB 编码值
+/// # Examples
+/// ```
+/// use timg::linear_to_srgb;
+/// assert_eq!(linear_to_srgb(0.0), 0.0);
+/// assert!((linear_to_srgb(1.0) - 1.0).abs() < 1e-6);
+/// assert!((linear_to_srgb(0.5) - 0.735_357).abs() < 1e-6);
+/// ```
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// 8 位 sRGB 值到线性光强的查找表
+fn srgb_u8_table() -> &'static [f32; 256] {
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0.0; 256];
+        for (i, x) in table.iter_mut().enumerate() {
+            *x = srgb_to_linear(i as f32 / 255.0);
+        }
+        table
+    })
+}
+
+/// 8 位 sRGB 值转换为线性光强
+/// # Examples
+/// ```
+/// use timg::srgb_u8_to_linear;
+/// assert!((srgb_u8_to_linear(128) - 0.215_861).abs() < 1e-6);
+/// ```
+pub fn srgb_u8_to_linear(value: u8) -> f32 {
+    srgb_u8_table()[value as usize]
+}
+
+/// 线性光强转换为 8 位 sRGB 值
+/// # Examples
+/// ```
+/// use timg::linear_to_srgb_u8;
+/// assert_eq!(linear_to_srgb_u8(0.5), 188);
+/// assert_eq!(linear_to_srgb_u8(2.0), 255);
+/// assert_eq!(linear_to_srgb_u8(-1.0), 0);
+/// ```
+pub fn linear_to_srgb_u8(value: f32) -> u8 {
+    (linear_to_srgb(value.clamp(0.0, 1.0)) * 255.0).round() as u8
+}
+
+/// 同 [`rgba_to_rgb`], 但在线性光下混合
+/// # Examples
+/// ```
+/// use timg::rgba_to_rgb_linear;
+/// assert_eq!(rgba_to_rgb_linear([255, 255, 255, 128], [0; 3]), [188; 3]);
+/// assert_eq!(rgba_to_rgb_linear([100, 149, 237, 200], [255; 3]),
+///            [152, 179, 241]);
+/// assert_eq!(rgba_to_rgb_linear([12, 34, 56, 255], [255; 3]), [12, 34, 56]);
+/// ```
+pub fn rgba_to_rgb_linear(foreground: Rgba, background: Rgb) -> Rgb {
+    let alpha = foreground[3] as f32 / 255.0;
+    let mut res = [0; 3];
+    for i in 0..3 {
+        let fg = srgb_u8_to_linear(foreground[i]);
+        let bg = srgb_u8_to_linear(background[i]);
+        res[i] = linear_to_srgb_u8(fg * alpha + bg * (1.0 - alpha));
+    }
+    res
+}
+
+/// 是否为每个通道 8 位的图片
+fn is_u8_image(img: &DynamicImage) -> bool {
+    matches!(img,
+        DynamicImage::ImageLuma8(_) | DynamicImage::ImageLumaA8(_)
+        | DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_))
+}
+
+/// 是否为浮点图片, 浮点图片的值已经是线性光强
+pub fn is_float_image(img: &DynamicImage) -> bool {
+    matches!(img,
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_))
+}
+
+/// 转换为预乘透明度的线性光图片, 以及是否有完全透明的像素
+///
+/// 仅整数图片视为 sRGB 编码
+fn to_linear(img: &DynamicImage, threads: usize) -> (Rgba32FImage, bool) {
+    let row_len = img.width() as usize * 4;
+    let has_clear = AtomicBool::new(false);
+    let weight = |a: f32| {
+        if a <= 0.0 {
+            has_clear.store(true, Ordering::Relaxed);
+        }
+        a
+    };
+    let res = if is_u8_image(img) {
+        // 8 位图片可以直接查表
+        let rgba = img.to_rgba8();
+        let mut res = vec![0.0; rgba.as_raw().len()];
+        par_bands(&mut res, row_len, threads, |first, band| {
+            let src = &rgba.as_raw()[first * row_len..];
+            for (out, pixel) in band.chunks_mut(4).zip(src.chunks(4)) {
+                let a = pixel[3] as f32 / 255.0;
+                let w = weight(a);
+                for i in 0..3 {
+                    out[i] = srgb_u8_to_linear(pixel[i]) * w;
+                }
+                out[3] = a;
+            }
+        });
+        Rgba32FImage::from_raw(rgba.width(), rgba.height(), res).unwrap()
+    } else {
+        let is_float = is_float_image(img);
+        let mut rgba = img.to_rgba32f();
+        par_bands(&mut rgba, row_len, threads, |_, band| {
+            for pixel in band.chunks_mut(4) {
+                let w = weight(pixel[3]);
+                for n in &mut pixel[..3] {
+                    let value = n.max(0.0);
+                    *n = if is_float { value } else { srgb_to_linear(value) } * w;
+                }
+            }
+        });
+        rgba
+    };
+    (res, has_clear.into_inner())
+}
+
+/// 源图片中像素未预乘的线性光颜色
+fn straight_color(img: &DynamicImage, x: SizeType, y: SizeType) -> [f32; 3] {
+    match img {
+        DynamicImage::ImageRgba32F(img) => {
+            let [r, g, b, _] = img.get_pixel(x, y).0;
+            [r, g, b].map(|x| x.max(0.0))
+        },
+        DynamicImage::ImageRgba16(img) => {
+            let [r, g, b, _] = img.get_pixel(x, y).0;
+            [r, g, b].map(|x| srgb_to_linear(x as f32 / u16::MAX as f32))
+        },
+        img => {
+            let [r, g, b, _] = img.get_pixel(x, y).0;
+            [r, g, b].map(srgb_u8_to_linear)
+        },
+    }
+}
+
+/// Resize in linear light, instead of the gamma-encoded values.
+///
+/// Filtering gamma-encoded values darkens edges and fine detail,
+/// this converts to linear light with premultiplied alpha,
+/// resizes, and converts back to sRGB.
+/// 8-bit images result in 8-bit, others keep the full precision and range
+/// in a 32-bit float image.
+/// Float images are linear light already, and stay linear.
+/// Fully transparent pixels keep their color, and the image is returned
+/// unchanged when the size is the same.
+/// All steps run in row bands on [`available_threads`].
+pub fn resize_linear(img: &DynamicImage,
+                     width: SizeType,
+                     height: SizeType,
+                     filter: FilterType) -> DynamicImage {
+    if [img.width(), img.height()] == [width, height] {
+        return img.clone();
+    }
+    let threads = available_threads();
+    let is_u8 = is_u8_image(img);
+    let is_float = is_float_image(img);
+    let (linear, has_clear) = to_linear(img, threads);
+    let mut resized = resize_buffer(&linear, width, height, filter, threads);
+    // 完全透明的像素预乘后没有颜色, 仅对缩放后完全透明的像素缩放未预乘的颜色
+    let straight: Vec<[f32; 3]> = if has_clear {
+        resample_where([img.width(), img.height()],
+                       [width, height],
+                       filter,
+                       threads,
+                       |x, y| straight_color(img, x, y),
+                       |x, y| resized.get_pixel(x, y)[3] <= 0.0)
+    } else {
+        Vec::new()
+    };
+    let row_len = width as usize * 4;
+    // `idx` 为像素在整个图片中的下标
+    let unpremultiply = |idx: usize, i: usize, pixel: &[f32]| {
+        let a = pixel[3].clamp(0.0, 1.0);
+        if a > 0.0 {
+            (pixel[i] / a).max(0.0)
+        } else {
+            straight.get(idx).map_or(0.0, |x| x[i].max(0.0))
+        }
+    };
+    if ! is_u8 {
+        par_bands(&mut resized, row_len, threads, |first, band| {
+            for (j, pixel) in band.chunks_mut(4).enumerate() {
+                for i in 0..3 {
+                    let idx = first * width as usize + j;
+                    let value = unpremultiply(idx, i, pixel);
+                    pixel[i] = if is_float { value } else { linear_to_srgb(value) };
+                }
+                pixel[3] = pixel[3].clamp(0.0, 1.0);
+            }
+        });
+        return DynamicImage::ImageRgba32F(resized);
+    }
+    let mut res = vec![0; resized.as_raw().len()];
+    par_bands(&mut res, row_len, threads, |first, band| {
+        let src = &resized.as_raw()[first * row_len..];
+        for (j, (out, pixel)) in band.chunks_mut(4).zip(src.chunks(4)).enumerate() {
+            for (i, n) in out[..3].iter_mut().enumerate() {
+                *n = linear_to_srgb_u8(
+                    unpremultiply(first * width as usize + j, i, pixel));
+            }
+            out[3] = (pixel[3].clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+    });
+    DynamicImage::ImageRgba8(RgbaImage::from_raw(width, height, res).unwrap())
+}
+
+#[test]
+fn resize_linear_test() {
+    use image::Rgb;
+
+    // 黑白相间缩小后, 线性光下的平均值为 0.5, 即 sRGB 的 188
+    let img = DynamicImage::ImageRgb8(image::RgbImage::from_fn(8, 8, |x, _| {
+        Rgb([if x & 1 == 0 { 0 } else { 255 }; 3])
+    }));
+    let res = resize_linear(&img, 1, 1, FilterType::Triangle).into_rgba8();
+    assert_eq!(res.get_pixel(0, 0).0, [188, 188, 188, 255]);
+    // 在 sRGB 下缩放则偏暗
+    let res = img.resize_exact(1, 1, FilterType::Triangle).into_rgba8();
+    assert!(res.get_pixel(0, 0).0[0] < 130);
+
+    // 透明的像素不应使颜色变暗
+    let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(4, 4, |x, _| {
+        if x < 2 {
+            image::Rgba([200, 100, 50, 255])
+        } else {
+            image::Rgba([0, 0, 0, 0])
+        }
+    }));
+    let res = resize_linear(&img, 1, 1, FilterType::Triangle).into_rgba8();
+    let [r, g, b, a] = res.get_pixel(0, 0).0;
+    assert_eq!([r, g, b], [200, 100, 50]);
+    assert!((127..=128).contains(&a));
+
+    // 完全透明的像素保留颜色, 大小相同时不经过预乘
+    let clear = DynamicImage::ImageRgba8(RgbaImage::from_fn(4, 4, |x, _| {
+        image::Rgba([200, 100, 50, if x == 0 { 255 } else { 0 }])
+    }));
+    let res = resize_linear(&clear, 4, 4, FilterType::Triangle);
+    assert_eq!(res, clear);
+    let res = resize_linear(&clear, 2, 2, FilterType::Triangle).into_rgba8();
+    assert_eq!(res.get_pixel(1, 1).0, [200, 100, 50, 0]);
+    let res = resize_linear(&clear.to_rgba32f().into(), 2, 2, FilterType::Nearest)
+        .into_rgba8();
+    assert_eq!(res.get_pixel(1, 0).0, [200, 100, 50, 0]);
+
+    // 16 位的图片
+    let img = DynamicImage::ImageRgb16(image::ImageBuffer::from_fn(2, 2, |x, _| {
+        Rgb([if x == 0 { 0 } else { u16::MAX }; 3])
+    }));
+    let res = resize_linear(&img, 1, 1, FilterType::Triangle);
+    assert!(matches!(res, DynamicImage::ImageRgba32F(_)));
+    assert_eq!(res.into_rgba8().get_pixel(0, 0).0, [188, 188, 188, 255]);
+
+    // 浮点图片超过 1 的值被保留
+    let img = DynamicImage::ImageRgb32F(image::ImageBuffer::from_pixel(
+            2, 2, Rgb([4.0, 0.5, 0.0])));
+    let res = resize_linear(&img, 1, 1, FilterType::Triangle).into_rgba32f();
+    let [r, g, b, a] = res.get_pixel(0, 0).0;
+    assert!((r - 4.0).abs() < 1e-3 && (g - 0.5).abs() < 1e-3);
+    assert_eq!([b, a], [0.0, 1.0]);
+
+    // 浮点图片已是线性光强, 不再经过 sRGB 的转换
+    let img = DynamicImage::ImageRgb32F(image::ImageBuffer::from_fn(2, 1, |x, _| {
+        Rgb([x as f32; 3])
+    }));
+    let res = resize_linear(&img, 1, 1, FilterType::Triangle).into_rgba32f();
+    assert!((res.get_pixel(0, 0).0[0] - 0.5).abs() < 1e-6);
+}
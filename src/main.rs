@@ -42,8 +42,52 @@ fn main() {
              .takes_value(true)
              .help(lines!(
                      "Set background colors."
+                     "Format: #rgb, #rrggbb, rgb(r,g,b), hsl(h,s%,l%), name,"
+                     "  none or terminal, 256-color index (0-255 or c0-c255)"
+                     "`none` is put first if not in the list,"
+                     "and a checkerboard is appended, see `--checker-*`"
                      "Default: 000000,888888,ffffff")))
 
+        .arg(Arg::with_name("term_bg")
+             .long("terminal-background")
+             .value_name("color")
+             .takes_value(true)
+             .help(lines!(
+                     "Terminal background color, used to blend transparent"
+                     "pixels on the `none` background."
+                     "Used when the terminal does not answer the OSC 11 query."
+                     "Default: 000000")))
+
+        .arg(Arg::with_name("checker_colors")
+             .long("checker-colors")
+             .value_name("colors")
+             .takes_value(true)
+             .help(lines!(
+                     "Set the two colors of the checkerboard background."
+                     "Default: 666666,999999")))
+
+        .arg(Arg::with_name("mask_color")
+             .long("mask-color")
+             .value_name("color")
+             .takes_value(true)
+             .help(lines!(
+                     "Color of the alpha mask channel view (`n`),"
+                     "covering the transparent parts"
+                     "Default: ff0000")))
+
+        .arg(Arg::with_name("checker_size")
+             .long("checker-size")
+             .value_name("num")
+             .takes_value(true)
+             .help(lines!(
+                     "Tile size of the checkerboard background, in screen pixels"
+                     "Range: num > 0"
+                     "Default: 4")))
+
+        .arg(Arg::with_name("checker_source")
+             .long("checker-source")
+             .help("Checkerboard tile size is in source image pixels"))
+
         .arg(Arg::with_name("zoom_ratio")
              .short("z")
              .long("zoom-ratio")
@@ -74,6 +118,24 @@ fn main() {
                      "Range: num >= 0"
                      "Default: 60")))
 
+        .arg(Arg::with_name("stats")
+             .long("stats")
+             .help(lines!(
+                     "Show bytes written per frame, frame time and throughput"
+                     "in the status line"
+                     "Switch in the viewer: `N`")))
+
+        .arg(Arg::with_name("adaptive")
+             .long("adaptive")
+             .value_name("budget")
+             .takes_value(true)
+             .help(lines!(
+                     "Tune the opt level automatically toward a budget per frame,"
+                     "bytes written (e.g. 20K, 1M)"
+                     "or time spent writing the output (e.g. 50ms)."
+                     "Adjusting opt by hand pauses it"
+                     "Switch in the viewer: `U`")))
+
         .arg(Arg::with_name("long_move_ratio")
              .short("l")
              .long("long-move-ratio")
@@ -84,9 +146,122 @@ fn main() {
                      "Range: num > 0"
                      "Default: 0.75")))
 
+        .arg(Arg::with_name("cell_aspect")
+             .long("cell-aspect")
+             .value_name("num")
+             .takes_value(true)
+             .help(lines!(
+                     "Height / width of a terminal character cell."
+                     "Default: query the terminal, or 2.0 if failed")))
+
+        .arg(Arg::with_name("no_linear")
+             .long("no-linear")
+             .help(lines!(
+                     "Blend alpha and resize on gamma-encoded sRGB values,"
+                     "instead of linear light. Faster, but darkens edges."
+                     "Switch in the viewer: `L`")))
+
+        .arg(Arg::with_name("no_progressive")
+             .long("no-progressive")
+             .help(lines!(
+                     "Render each change directly in the chosen quality,"
+                     "instead of a fast nearest-neighbor preview first")))
+
+        .arg(Arg::with_name("ascii")
+             .short("a")
+             .long("ascii")
+             .help(lines!(
+                     "Start in ASCII-art mode, output without colors."
+                     "Switch in the viewer: `t`")))
+
+        .arg(Arg::with_name("ramp")
+             .long("ramp")
+             .value_name("chars")
+             .takes_value(true)
+             .help(lines!(
+                     "Character ramp of ASCII-art mode, from dark to bright"
+                     "Default: \" .:-=+*#%@\"")))
+
+        .arg(Arg::with_name("edges")
+             .long("edges")
+             .help(lines!(
+                     "Use edge-aware glyphs `-|/\\` in ASCII-art mode"
+                     "Switch in the viewer: `T`")))
+
+        .arg(Arg::with_name("hist_bg")
+             .long("histogram-background")
+             .value_name("color")
+             .takes_value(true)
+             .help(lines!(
+                     "Background color of the histogram panel (`b`)"
+                     "Default: 202020")))
+
+        .arg(Arg::with_name("inspector_color")
+             .long("inspector-color")
+             .value_name("color")
+             .takes_value(true)
+             .help(lines!(
+                     "Color of the pixel inspector marker (`p`)"
+                     "Default: ff00ff")))
+
+        .arg(Arg::with_name("raw")
+             .long("raw")
+             .value_name("layout")
+             .takes_value(true)
+             .help(lines!(
+                     "Read FILE as headerless pixels instead of an image file"
+                     "Format: WxH:FORMAT[:stride][:offset], stride and offset in bytes"
+                     "FORMAT: gray8 gray16 rgb565 rgb888 bgr888 rgba8888 bgra8888"
+                     "        yuv420 (i420) nv12 nv21"
+                     "Example: 640x480:nv12, 320x240:rgb565:640:16")))
+
+        .arg(Arg::with_name("npy_range")
+             .long("npy-range")
+             .value_name("range")
+             .takes_value(true)
+             .help(lines!(
+                     "Value range of `.npy` arrays mapped to black and white"
+                     "type: full range of integers, 0 to 1 of floats"
+                     "auto: minimum to maximum value of the array"
+                     "MIN,MAX: the given range, e.g. -1,1"
+                     "Default: auto")))
+
+        .arg(Arg::with_name("watch")
+             .short("w")
+             .long("watch")
+             .help(lines!(
+                     "Reload FILE when it changes on disk,"
+                     "zoom, position and transforms are kept."
+                     "A directory FILE is always watched, showing its newest image"
+                     "Pause in the viewer: `F`")))
+
+        .arg(Arg::with_name("slideshow")
+             .long("slideshow")
+             .value_name("secs")
+             .takes_value(true)
+             .help(lines!(
+                     "Start a slideshow of the FILEs, advancing every secs seconds."
+                     "Play/pause in the viewer: `Space`, interval: `JK`,"
+                     "previous/next image: `,.`")))
+
+        .arg(Arg::with_name("stream")
+             .long("stream")
+             .help(lines!(
+                     "Keep reading frames from FILE, a FIFO or `-` (stdin, the default)"
+                     "Formats: Y4M, concatenated PPM/PGM or PNG,"
+                     "         PNG with a 4-byte big-endian length before each")))
+
+        .arg(Arg::with_name("dump")
+             .long("dump")
+             .help(lines!(
+                     "Print the whole image as ASCII-art to stdout and exit."
+                     "Not interactive, the size is given by `--term-size`")))
+
         .args(&[
-            Arg::with_name("FILE").index(1)
-                .help("Target file")
+            Arg::with_name("FILE").index(1).multiple(true)
+                .help(lines!(
+                    "Target files, or the stream input with `--stream`."
+                    "A directory shows the newest image in it"))
         ])
 
         .help_short("H") // help flag
@@ -0,0 +1,48 @@
+/// Keeps the value computed for the latest key only.
+///
+/// Used for the stages of rendering a frame, so that a stage is redone
+/// only when its inputs changed.
+#[derive(Debug, Clone)]
+pub struct Memo<K, V> {
+    entry: Option<(K, V)>,
+}
+impl<K: PartialEq, V> Memo<K, V> {
+    pub fn new() -> Self {
+        Self { entry: None }
+    }
+    pub fn is_cached(&self, key: &K) -> bool {
+        self.entry.as_ref().is_some_and(|(k, _)| k == key)
+    }
+    /// 键与上一次的不同时, 使用 `f` 重新计算
+    /// # Examples
+    /// ```
+    /// use timg::Memo;
+    /// let mut memo = Memo::new();
+    /// let mut count = 0;
+    /// for key in [1, 1, 2, 2, 1] {
+    ///     assert_eq!(*memo.get_or_insert_with(key, || { count += 1; key * 10 }),
+    ///                key * 10);
+    /// }
+    /// assert_eq!(count, 3);
+    /// memo.clear();
+    /// assert!(! memo.is_cached(&1));
+    /// ```
+    pub fn get_or_insert_with<F>(&mut self, key: K, f: F) -> &V
+    where F: FnOnce() -> V,
+    {
+        if ! self.is_cached(&key) {
+            self.entry = None; // 先释放旧的值
+            self.entry = Some((key, f()));
+        }
+        &self.entry.as_ref().unwrap().1
+    }
+    /// 输入中未包含在键里的部分 (例如图片本身) 改变时调用
+    pub fn clear(&mut self) {
+        self.entry = None;
+    }
+}
+impl<K: PartialEq, V> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,101 @@
+use image::{
+    imageops::FilterType,
+    DynamicImage,
+};
+
+use super::*;
+
+/// 生成下一层时使用的滤波器, 缩小一半时近似于方框平均
+const LEVEL_FILTER: FilterType = FilterType::Triangle;
+
+/// Lazily built mipmap pyramid of an image.
+///
+/// Level 0 is the image itself, each next level is half the size
+/// of the previous one, so that a zoomed out frame is resampled
+/// from a small level instead of the whole huge image.
+#[derive(Debug, Clone, Default)]
+pub struct Pyramid {
+    /// 第 1 层开始已生成的层级
+    levels: Vec<DynamicImage>,
+    /// 已生成的层级是否在线性光下缩小
+    linear: bool,
+}
+impl Pyramid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// 图片改变时调用
+    pub fn clear(&mut self) {
+        self.levels.clear();
+    }
+    /// 每个源像素对应的输出像素不超过 `scale` 时, 分辨率仍不低于输出的最小层级
+    /// # Examples
+    /// ```
+    /// use timg::Pyramid;
+    /// assert_eq!(Pyramid::level_for_scale(0.5), 0);
+    /// assert_eq!(Pyramid::level_for_scale(1.9), 0);
+    /// assert_eq!(Pyramid::level_for_scale(2.0), 1);
+    /// assert_eq!(Pyramid::level_for_scale(7.9), 2);
+    /// assert_eq!(Pyramid::level_for_scale(8.0), 3);
+    /// ```
+    pub fn level_for_scale(scale: Float) -> u32 {
+        if scale < 2.0 {
+            0
+        } else {
+            scale.log2().floor() as u32
+        }
+    }
+    /// 第 `n` 层的图片, 按需从上一层生成, 最多缩小到 1 像素
+    pub fn level<'a>(&'a mut self,
+                     base: &'a DynamicImage,
+                     n: u32,
+                     linear: bool) -> &'a DynamicImage {
+        if linear != self.linear {
+            self.levels.clear();
+            self.linear = linear;
+        }
+        while self.levels.len() < n as usize {
+            let prev = self.levels.last().unwrap_or(base);
+            if prev.width() == 1 && prev.height() == 1 {
+                break;
+            }
+            let [width, height] = [prev.width(), prev.height()]
+                .map(|len| len.div_ceil(2));
+            let next = if linear {
+                resize_linear(prev, width, height, LEVEL_FILTER)
+            } else {
+                resize_parallel(prev, width, height, LEVEL_FILTER,
+                                available_threads())
+            };
+            self.levels.push(next);
+        }
+        match n {
+            0 => base,
+            n => &self.levels[(n as usize).min(self.levels.len()) - 1],
+        }
+    }
+}
+
+#[test]
+fn pyramid_test() {
+    use image::RgbImage;
+
+    let base = DynamicImage::ImageRgb8(RgbImage::from_fn(10, 5, |x, y| {
+        image::Rgb([(x * 20) as u8, (y * 40) as u8, 128])
+    }));
+    let mut pyramid = Pyramid::new();
+    assert_eq!(pyramid.level(&base, 0, false), &base);
+    let sizes = (0..6)
+        .map(|n| {
+            let level = pyramid.level(&base, n, false);
+            [level.width(), level.height()]
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(sizes, [[10, 5], [5, 3], [3, 2], [2, 1], [1, 1], [1, 1]]);
+    assert_eq!(pyramid.levels.len(), 4);
+    // 换为线性光时重新生成
+    pyramid.level(&base, 1, true);
+    assert_eq!(pyramid.levels.len(), 1);
+    pyramid.clear();
+    assert!(pyramid.levels.is_empty());
+}
@@ -0,0 +1,305 @@
+use std::{
+    error::Error,
+    fmt,
+    str::FromStr,
+};
+
+use image::{
+    DynamicImage,
+    ImageBuffer,
+    Rgb32FImage,
+    Rgba32FImage,
+};
+
+use super::*;
+
+/// `.npy` 文件开头的魔数
+pub const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+/// 读取 `.npy` 时的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NpyError {
+    /// 不是 `.npy` 文件
+    Magic,
+    /// 头部格式错误
+    Header(String),
+    /// 不支持的数据类型
+    Dtype(String),
+    /// 不支持的形状, 仅支持 `(H, W)` 与 `(H, W, C)`, C 为 1 到 4
+    Shape(Vec<usize>),
+    /// 数据不足, (需要的, 实际的) 字节数
+    TooShort(usize, usize),
+    /// 数据的长度超出了地址范围
+    TooLarge,
+    /// 范围格式错误
+    Range(String),
+}
+impl fmt::Display for NpyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Magic => write!(f, "not a npy file"),
+            Self::Header(s) => write!(f, "invalid npy header {:?}", s),
+            Self::Dtype(s) => write!(
+                f, "unsupported dtype {:?}, expected uint8 uint16 or float32", s),
+            Self::Shape(shape) => write!(
+                f, "unsupported shape {:?}, expected (H, W) or (H, W, C<=4)", shape),
+            Self::TooShort(need, got) => write!(
+                f, "npy data too short, need {} bytes, got {}", need, got),
+            Self::TooLarge => write!(f, "npy array too large"),
+            Self::Range(s) => write!(
+                f, "{:?} is not `type`, `auto` or `MIN,MAX`", s),
+        }
+    }
+}
+impl Error for NpyError {}
+
+/// 数组的值映射到图片的方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NpyRange {
+    /// 按数据类型的范围, 整数类型的完整范围, 浮点为 0 到 1
+    Type,
+    /// 数组中的最小值到最大值
+    Auto,
+    /// 给定的最小值到最大值
+    Fixed(f32, f32),
+}
+impl FromStr for NpyRange {
+    type Err = NpyError;
+    /// # Examples
+    /// ```
+    /// use timg::NpyRange;
+    /// assert_eq!("auto".parse(), Ok(NpyRange::Auto));
+    /// assert_eq!("-1,1".parse(), Ok(NpyRange::Fixed(-1.0, 1.0)));
+    /// assert!("1,1".parse::<NpyRange>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || NpyError::Range(s.to_string());
+        match s.trim() {
+            "type" => Ok(Self::Type),
+            "auto" => Ok(Self::Auto),
+            x => {
+                let (min, max) = x.split_once(',').ok_or_else(err)?;
+                let min: f32 = min.trim().parse().map_err(|_| err())?;
+                let max: f32 = max.trim().parse().map_err(|_| err())?;
+                if min < max { Ok(Self::Fixed(min, max)) } else { Err(err()) }
+            },
+        }
+    }
+}
+
+/// 数组的数据
+#[derive(Debug, Clone, PartialEq)]
+pub enum NpyData {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    F32(Vec<f32>),
+}
+
+/// An array read from a `.npy` file, in C order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NpyArray {
+    pub shape: Vec<usize>,
+    pub data: NpyData,
+}
+
+/// 获取头部字典中一个键的值的文本
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}'", key))? + key.len() + 2;
+    let rest = header[start..].trim_start().strip_prefix(':')?.trim_start();
+    let end = match rest.chars().next()? {
+        '(' => rest.find(')')? + 1,
+        '\'' => rest[1..].find('\'')? + 2,
+        _ => rest.find([',', '}']).unwrap_or(rest.len()),
+    };
+    Some(rest[..end].trim())
+}
+
+impl NpyArray {
+    /// 解析 `.npy` 文件的内容
+    pub fn read(bytes: &[u8]) -> Result<Self, NpyError> {
+        let rest = bytes.strip_prefix(NPY_MAGIC).ok_or(NpyError::Magic)?;
+        let header_err = || NpyError::Header(String::new());
+        // 版本 1 的头部长度为 u16, 之后的版本为 u32
+        let (header_len, rest) = match rest {
+            [1, _, a, b, rest @ ..] => (u16::from_le_bytes([*a, *b]) as usize, rest),
+            [2 | 3, _, a, b, c, d, rest @ ..]
+                => (u32::from_le_bytes([*a, *b, *c, *d]) as usize, rest),
+            _ => return Err(header_err()),
+        };
+        if rest.len() < header_len {
+            return Err(header_err());
+        }
+        let (header, data) = rest.split_at(header_len);
+        let header = String::from_utf8_lossy(header);
+        let err = || NpyError::Header(header.trim().to_string());
+        if header_value(&header, "fortran_order") != Some("False") {
+            return Err(err());
+        }
+        let shape: Vec<usize> = header_value(&header, "shape")
+            .and_then(|x| x.strip_prefix('('))
+            .and_then(|x| x.strip_suffix(')'))
+            .ok_or_else(err)?
+            .split(',')
+            .map(str::trim)
+            .filter(|x| ! x.is_empty())
+            .map(|x| x.parse().map_err(|_| err()))
+            .collect::<Result<_, _>>()?;
+        // 宽高须能放进 SizeType
+        let valid_size = |len: usize| (1..=SizeType::MAX as usize).contains(&len);
+        let valid_shape = match shape[..] {
+            [h, w] => valid_size(h) && valid_size(w),
+            [h, w, c] => valid_size(h) && valid_size(w) && (1..=4).contains(&c),
+            _ => false,
+        };
+        if ! valid_shape {
+            return Err(NpyError::Shape(shape));
+        }
+        let descr = header_value(&header, "descr")
+            .map(|x| x.trim_matches('\''))
+            .ok_or_else(err)?;
+        let (item_size, big_endian) = match descr {
+            "|u1" | "<u1" | ">u1" | "=u1" => (1, false),
+            "<u2" | "=u2" => (2, false),
+            ">u2" => (2, true),
+            "<f4" | "=f4" => (4, false),
+            ">f4" => (4, true),
+            _ => return Err(NpyError::Dtype(descr.to_string())),
+        };
+        let len = shape.iter()
+            .try_fold(item_size, |len: usize, &x| len.checked_mul(x))
+            .ok_or(NpyError::TooLarge)?;
+        if data.len() < len {
+            return Err(NpyError::TooShort(len, data.len()));
+        }
+        let data = &data[..len];
+        let data = match &descr[1..] {
+            "u1" => NpyData::U8(data.to_vec()),
+            "u2" => NpyData::U16(data.chunks_exact(2).map(|x| {
+                let x = [x[0], x[1]];
+                if big_endian { u16::from_be_bytes(x) } else { u16::from_le_bytes(x) }
+            }).collect()),
+            _ => NpyData::F32(data.chunks_exact(4).map(|x| {
+                let x = [x[0], x[1], x[2], x[3]];
+                if big_endian { f32::from_be_bytes(x) } else { f32::from_le_bytes(x) }
+            }).collect()),
+        };
+        Ok(Self { shape, data })
+    }
+    /// 图片的宽高与通道数
+    pub fn dimensions(&self) -> (SizeType, SizeType, usize) {
+        (self.shape[1] as SizeType,
+         self.shape[0] as SizeType,
+         self.shape.get(2).copied().unwrap_or(1))
+    }
+    /// 数组中的有限值的最小值与最大值
+    pub fn value_range(&self) -> (f32, f32) {
+        let values: Box<dyn Iterator<Item = f32>> = match &self.data {
+            NpyData::U8(x) => Box::new(x.iter().map(|&x| x as f32)),
+            NpyData::U16(x) => Box::new(x.iter().map(|&x| x as f32)),
+            NpyData::F32(x) => Box::new(x.iter().copied().filter(|x| x.is_finite())),
+        };
+        values.fold((f32::INFINITY, f32::NEG_INFINITY),
+                    |(min, max), x| (min.min(x), max.max(x)))
+    }
+    /// 转换为图片, 两个通道时视为灰度与透明度
+    ///
+    /// [`NpyRange::Type`] 的整数数组保持原本的类型, 其它的转换为浮点图片
+    pub fn to_image(&self, range: NpyRange) -> DynamicImage {
+        let (width, height, channels) = self.dimensions();
+        macro_rules! native {
+            ( $data:expr, $luma:ident, $luma_a:ident, $rgb:ident, $rgba:ident ) => {{
+                let data = $data.clone();
+                match channels {
+                    1 => DynamicImage::$luma(
+                        ImageBuffer::from_raw(width, height, data).unwrap()),
+                    2 => DynamicImage::$luma_a(
+                        ImageBuffer::from_raw(width, height, data).unwrap()),
+                    3 => DynamicImage::$rgb(
+                        ImageBuffer::from_raw(width, height, data).unwrap()),
+                    _ => DynamicImage::$rgba(
+                        ImageBuffer::from_raw(width, height, data).unwrap()),
+                }
+            }};
+        }
+        let (min, max) = match (range, &self.data) {
+            (NpyRange::Type, NpyData::U8(data))
+                => return native!(data, ImageLuma8, ImageLumaA8, ImageRgb8, ImageRgba8),
+            (NpyRange::Type, NpyData::U16(data))
+                => return native!(data, ImageLuma16, ImageLumaA16, ImageRgb16, ImageRgba16),
+            (NpyRange::Type, NpyData::F32(_)) => (0.0, 1.0),
+            (NpyRange::Auto, _) => self.value_range(),
+            (NpyRange::Fixed(min, max), _) => (min, max),
+        };
+        let scale = if max > min { 1.0 / (max - min) } else { 1.0 };
+        let values: Vec<f32> = match &self.data {
+            NpyData::U8(x) => x.iter().map(|&x| x as f32).collect(),
+            NpyData::U16(x) => x.iter().map(|&x| x as f32).collect(),
+            NpyData::F32(x) => x.clone(),
+        };
+        let get = |x: SizeType, y: SizeType, c: usize| {
+            let value = values[(y as usize * width as usize + x as usize) * channels + c];
+            if value.is_nan() { 0.0 } else { (value - min) * scale }
+        };
+        match channels {
+            1 => DynamicImage::ImageRgb32F(Rgb32FImage::from_fn(width, height, |x, y| {
+                image::Rgb([get(x, y, 0); 3])
+            })),
+            2 => DynamicImage::ImageRgba32F(Rgba32FImage::from_fn(width, height, |x, y| {
+                let v = get(x, y, 0);
+                image::Rgba([v, v, v, get(x, y, 1).clamp(0.0, 1.0)])
+            })),
+            3 => DynamicImage::ImageRgb32F(Rgb32FImage::from_fn(width, height, |x, y| {
+                image::Rgb([get(x, y, 0), get(x, y, 1), get(x, y, 2)])
+            })),
+            _ => DynamicImage::ImageRgba32F(Rgba32FImage::from_fn(width, height, |x, y| {
+                image::Rgba([get(x, y, 0), get(x, y, 1), get(x, y, 2),
+                             get(x, y, 3).clamp(0.0, 1.0)])
+            })),
+        }
+    }
+}
+
+#[test]
+fn npy_test() {
+    let npy = |descr: &str, shape: &str, data: &[u8]| {
+        let header = format!(
+            "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+            descr, shape);
+        let mut res = NPY_MAGIC.to_vec();
+        res.extend([1, 0]);
+        res.extend((header.len() as u16).to_le_bytes());
+        res.extend(header.as_bytes());
+        res.extend(data);
+        res
+    };
+    let arr = NpyArray::read(&npy("|u1", "(2, 3)", &[0, 1, 2, 3, 4, 5])).unwrap();
+    assert_eq!(arr.dimensions(), (3, 2, 1));
+    assert_eq!(arr.value_range(), (0.0, 5.0));
+    let img = arr.to_image(NpyRange::Type);
+    assert_eq!(img.as_luma8().unwrap().get_pixel(2, 1).0, [5]);
+    let img = arr.to_image(NpyRange::Auto).into_rgb32f();
+    assert_eq!(img.get_pixel(2, 1).0, [1.0; 3]);
+    assert_eq!(img.get_pixel(0, 0).0, [0.0; 3]);
+
+    let data: Vec<u8> = [0.5_f32, -1.0, 3.0, f32::NAN].iter()
+        .flat_map(|x| x.to_le_bytes()).collect();
+    let arr = NpyArray::read(&npy("<f4", "(1, 1, 4)", &data)).unwrap();
+    assert_eq!(arr.value_range(), (-1.0, 3.0));
+    let img = arr.to_image(NpyRange::Fixed(-1.0, 3.0)).into_rgba32f();
+    assert_eq!(img.get_pixel(0, 0).0, [0.375, 0.0, 1.0, 0.0]);
+
+    let arr = NpyArray::read(&npy(">u2", "(1, 1)", &[0x12, 0x34])).unwrap();
+    assert_eq!(arr.data, NpyData::U16(vec![0x1234]));
+
+    assert_eq!(NpyArray::read(&npy("<f8", "(1, 1)", &[0; 8])),
+               Err(NpyError::Dtype("<f8".to_string())));
+    assert_eq!(NpyArray::read(&npy("|u1", "(2, 2, 5)", &[0; 20])),
+               Err(NpyError::Shape(vec![2, 2, 5])));
+    assert_eq!(NpyArray::read(&npy("|u1", "(2, 2)", &[0; 3])),
+               Err(NpyError::TooShort(4, 3)));
+    assert_eq!(NpyArray::read(&npy("|u1", "(4294967296, 1)", &[0])),
+               Err(NpyError::Shape(vec![1 << 32, 1])));
+    assert_eq!(NpyArray::read(&npy("<f4", "(4294967295, 4294967295, 4)", &[0])),
+               Err(NpyError::TooLarge));
+    assert_eq!(NpyArray::read(b"PNG"), Err(NpyError::Magic));
+}
@@ -0,0 +1,92 @@
+use image::DynamicImage;
+
+/// Accumulated flips and rotations of the image.
+///
+/// Kept apart from the pixels, so that they can be applied again
+/// to a new image of a stream or a reloaded file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Orientation {
+    /// 在水平翻转之后, 顺时针旋转的 90 度次数
+    pub rotate: u8,
+    /// 首先水平翻转
+    pub flip: bool,
+}
+impl Orientation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+    /// 在当前的结果上水平翻转
+    pub fn fliph(&mut self) {
+        self.rotate = (4 - self.rotate) & 3;
+        self.flip = ! self.flip;
+    }
+    /// 在当前的结果上垂直翻转, 等同于水平翻转再旋转 180 度
+    pub fn flipv(&mut self) {
+        self.rotate = (6 - self.rotate) & 3;
+        self.flip = ! self.flip;
+    }
+    /// 在当前的结果上顺时针旋转 90 度
+    pub fn rotate90(&mut self) {
+        self.rotate = (self.rotate + 1) & 3;
+    }
+    pub fn rotate270(&mut self) {
+        self.rotate = (self.rotate + 3) & 3;
+    }
+    /// # Examples
+    /// ```
+    /// # use image::{DynamicImage, GrayImage};
+    /// use timg::Orientation;
+    /// let img = DynamicImage::ImageLuma8(GrayImage::new(3, 2));
+    /// let mut orientation = Orientation::new();
+    /// orientation.rotate90();
+    /// assert_eq!(orientation.apply(&img).width(), 2);
+    /// // 旋转 180 度再垂直翻转, 即为水平翻转
+    /// orientation.rotate90();
+    /// orientation.flipv();
+    /// assert_eq!(orientation, Orientation { rotate: 0, flip: true });
+    /// ```
+    pub fn apply(&self, img: &DynamicImage) -> DynamicImage {
+        let flipped;
+        let img = if self.flip {
+            flipped = img.fliph();
+            &flipped
+        } else {
+            img
+        };
+        match self.rotate {
+            1 => img.rotate90(),
+            2 => img.rotate180(),
+            3 => img.rotate270(),
+            _ => img.clone(),
+        }
+    }
+}
+
+#[test]
+fn orientation_test() {
+    use image::GrayImage;
+
+    let img = DynamicImage::ImageLuma8(GrayImage::from_fn(3, 2, |x, y| {
+        image::Luma([(y * 3 + x) as u8])
+    }));
+    // 与直接对图片依次操作的结果一致
+    let op = |i: usize, orientation: &mut Orientation, img: &DynamicImage| {
+        match i {
+            0 => { orientation.fliph(); img.fliph() },
+            1 => { orientation.flipv(); img.flipv() },
+            2 => { orientation.rotate90(); img.rotate90() },
+            _ => { orientation.rotate270(); img.rotate270() },
+        }
+    };
+    for seq in [[0, 2, 1], [2, 0, 3], [1, 1, 2], [3, 0, 2], [2, 2, 0]] {
+        let mut orientation = Orientation::new();
+        let mut expected = img.clone();
+        for i in seq {
+            expected = op(i, &mut orientation, &expected);
+            assert_eq!(orientation.apply(&img), expected, "{:?}", seq);
+        }
+    }
+}
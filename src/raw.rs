@@ -0,0 +1,339 @@
+use std::{
+    error::Error,
+    fmt,
+    str::FromStr,
+};
+
+use image::{
+    DynamicImage,
+    GrayImage,
+    ImageBuffer,
+    Luma,
+    RgbImage,
+    RgbaImage,
+};
+
+use super::*;
+
+/// 无头的原始像素格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawFormat {
+    Gray8,
+    /// 小端序
+    Gray16,
+    /// 小端序, 高位为红色
+    Rgb565,
+    Rgb888,
+    Bgr888,
+    Rgba8888,
+    Bgra8888,
+    /// I420, Y U V 三个平面, 色度的宽高各为一半
+    Yuv420,
+    /// Y 平面后为 UV 交错的平面
+    Nv12,
+    /// Y 平面后为 VU 交错的平面
+    Nv21,
+}
+impl RawFormat {
+    pub const NAMES: &'static [(&'static str, Self)] = &[
+        ("gray8", Self::Gray8), ("gray", Self::Gray8),
+        ("gray16", Self::Gray16),
+        ("rgb565", Self::Rgb565),
+        ("rgb888", Self::Rgb888), ("rgb", Self::Rgb888),
+        ("bgr888", Self::Bgr888), ("bgr", Self::Bgr888),
+        ("rgba8888", Self::Rgba8888), ("rgba", Self::Rgba8888),
+        ("bgra8888", Self::Bgra8888), ("bgra", Self::Bgra8888),
+        ("yuv420", Self::Yuv420), ("i420", Self::Yuv420),
+        ("nv12", Self::Nv12),
+        ("nv21", Self::Nv21),
+    ];
+    /// 第一个平面中每个像素的字节数
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            Self::Gray8 | Self::Yuv420 | Self::Nv12 | Self::Nv21 => 1,
+            Self::Gray16 | Self::Rgb565 => 2,
+            Self::Rgb888 | Self::Bgr888 => 3,
+            Self::Rgba8888 | Self::Bgra8888 => 4,
+        }
+    }
+}
+impl FromStr for RawFormat {
+    type Err = RawError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+        Self::NAMES.iter()
+            .find(|(name, _)| *name == lower)
+            .map(|&(_, format)| format)
+            .ok_or_else(|| RawError::Format(s.to_string()))
+    }
+}
+
+/// 读取原始像素时的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RawError {
+    /// 布局字符串的格式错误
+    Layout(String),
+    /// 未知的像素格式
+    Format(String),
+    /// 数据不足, (需要的, 实际的) 字节数
+    TooShort(usize, usize),
+    /// 行宽小于一行像素, (行宽, 一行像素的) 字节数
+    Stride(usize, usize),
+    /// 所需要的数据长度超出了地址范围
+    TooLarge,
+}
+impl fmt::Display for RawError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Layout(s) => write!(
+                f, "{:?} is not a `WxH:FORMAT[:stride][:offset]` layout", s),
+            Self::Format(s) => write!(
+                f, "unknown raw format {:?}, expected one of: {}", s,
+                RawFormat::NAMES.iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>().join(" ")),
+            Self::TooShort(need, got) => write!(
+                f, "raw data too short, need {} bytes, got {}", need, got),
+            Self::Stride(stride, row) => write!(
+                f, "stride {} is shorter than a row of {} bytes", stride, row),
+            Self::TooLarge => write!(f, "raw layout too large"),
+        }
+    }
+}
+impl Error for RawError {}
+
+/// BT.601 有限范围的 YUV 转换为 RGB
+/// # Examples
+/// ```
+/// use timg::yuv_to_rgb;
+/// assert_eq!(yuv_to_rgb(16, 128, 128), [0; 3]);
+/// assert_eq!(yuv_to_rgb(235, 128, 128), [255; 3]);
+/// assert_eq!(yuv_to_rgb(81, 90, 240), [254, 0, 0]);
+/// ```
+pub fn yuv_to_rgb(y: u8, u: u8, v: u8) -> [u8; 3] {
+    let c = (y as Float - 16.0) * 1.164;
+    let d = u as Float - 128.0;
+    let e = v as Float - 128.0;
+    [c + 1.596 * e, c - 0.392 * d - 0.813 * e, c + 2.017 * d]
+        .map(|x| x.round().clamp(0.0, 255.0) as u8)
+}
+
+/// Layout of a headerless pixel buffer, `WxH:FORMAT[:stride][:offset]`.
+///
+/// `stride` is the bytes of a row in the first plane,
+/// `offset` is the bytes skipped at the start of the data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawLayout {
+    pub width: SizeType,
+    pub height: SizeType,
+    pub format: RawFormat,
+    pub stride: Option<usize>,
+    pub offset: usize,
+}
+impl FromStr for RawLayout {
+    type Err = RawError;
+    /// # Examples
+    /// ```
+    /// use timg::{RawFormat, RawLayout};
+    /// let layout: RawLayout = "640x480:nv12:704:16".parse().unwrap();
+    /// assert_eq!(layout, RawLayout {
+    ///     width: 640, height: 480, format: RawFormat::Nv12,
+    ///     stride: Some(704), offset: 16,
+    /// });
+    /// let layout: RawLayout = "2x2:RGB565".parse().unwrap();
+    /// assert_eq!((layout.stride, layout.offset), (None, 0));
+    /// assert!("2x2".parse::<RawLayout>().is_err());
+    /// assert!("2x2:rgb:".parse::<RawLayout>().is_ok());
+    /// assert!("4x2:rgb:2".parse::<RawLayout>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || RawError::Layout(s.to_string());
+        let mut parts = s.trim().split(':');
+        let (width, height) = parts.next()
+            .and_then(|size| size.split_once(['x', 'X']))
+            .ok_or_else(err)?;
+        let parse_size = |x: &str| x.parse::<SizeType>().ok().filter(|&x| x != 0);
+        let (width, height) = parse_size(width).zip(parse_size(height))
+            .ok_or_else(err)?;
+        let format = parts.next().ok_or_else(err)?.parse()?;
+        // 为空时使用默认值
+        let mut opt_num = || -> Result<Option<usize>, RawError> {
+            match parts.next() {
+                None | Some("") => Ok(None),
+                Some(x) => x.parse().map(Some).map_err(|_| err()),
+            }
+        };
+        let stride = opt_num()?;
+        let offset = opt_num()?.unwrap_or(0);
+        if parts.next().is_some() {
+            return Err(err());
+        }
+        let layout = Self { width, height, format, stride, offset };
+        layout.data_len()?;
+        Ok(layout)
+    }
+}
+impl RawLayout {
+    /// 第一个平面每行的字节数
+    pub fn stride(&self) -> usize {
+        self.stride.unwrap_or(self.width as usize * self.format.bytes_per_pixel())
+    }
+    /// 所需要的数据长度, 包括 `offset`
+    ///
+    /// 行宽小于一行像素或长度溢出时返回错误,
+    /// YUV420 色度平面的行宽为一半, 因此只需检查 NV12 / NV21 交错的色度行
+    pub fn data_len(&self) -> Result<usize, RawError> {
+        let stride = self.stride();
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let row_len = width * self.format.bytes_per_pixel();
+        let chroma_row_len = width.div_ceil(2) * 2;
+        let is_nv = matches!(self.format, RawFormat::Nv12 | RawFormat::Nv21);
+        if stride < row_len {
+            return Err(RawError::Stride(stride, row_len));
+        }
+        if is_nv && stride < chroma_row_len {
+            return Err(RawError::Stride(stride, chroma_row_len));
+        }
+        let chroma_rows = height.div_ceil(2);
+        let luma_len = stride.checked_mul(height);
+        let len = match self.format {
+            RawFormat::Yuv420 => stride.div_ceil(2)
+                .checked_mul(chroma_rows * 2)
+                .zip(luma_len)
+                .and_then(|(chroma, luma)| luma.checked_add(chroma)),
+            RawFormat::Nv12 | RawFormat::Nv21 => {
+                // 最后一行可以不包含填充
+                stride.checked_mul(chroma_rows - 1)
+                    .zip(luma_len)
+                    .and_then(|(chroma, luma)| luma.checked_add(chroma))
+                    .and_then(|len| len.checked_add(chroma_row_len))
+            },
+            _ => luma_len,
+        };
+        len.and_then(|len| len.checked_add(self.offset))
+            .ok_or(RawError::TooLarge)
+    }
+    /// 由原始数据构建图片
+    pub fn decode(&self, data: &[u8]) -> Result<DynamicImage, RawError> {
+        let data_len = self.data_len()?;
+        if data.len() < data_len {
+            return Err(RawError::TooShort(data_len, data.len()));
+        }
+        let data = &data[self.offset..];
+        let stride = self.stride();
+        let bpp = self.format.bytes_per_pixel();
+        let (width, height) = (self.width, self.height);
+        // 第一个平面中像素的字节
+        let pixel = |x: SizeType, y: SizeType| {
+            let start = y as usize * stride + x as usize * bpp;
+            &data[start..start + bpp]
+        };
+        let luma_len = stride * height as usize;
+        Ok(match self.format {
+            RawFormat::Gray8 => DynamicImage::ImageLuma8(
+                GrayImage::from_fn(width, height, |x, y| Luma([pixel(x, y)[0]]))),
+            RawFormat::Gray16 => DynamicImage::ImageLuma16(
+                ImageBuffer::from_fn(width, height, |x, y| {
+                    let p = pixel(x, y);
+                    Luma([u16::from_le_bytes([p[0], p[1]])])
+                })),
+            RawFormat::Rgb565 => DynamicImage::ImageRgb8(
+                RgbImage::from_fn(width, height, |x, y| {
+                    let p = pixel(x, y);
+                    let n = u16::from_le_bytes([p[0], p[1]]) as u32;
+                    // 将低位用高位填充, 使得最大值为 255
+                    let [r, g, b] = [n >> 11, (n >> 5) & 0x3f, n & 0x1f];
+                    image::Rgb([(r << 3 | r >> 2) as u8,
+                                (g << 2 | g >> 4) as u8,
+                                (b << 3 | b >> 2) as u8])
+                })),
+            RawFormat::Rgb888 | RawFormat::Bgr888 => DynamicImage::ImageRgb8(
+                RgbImage::from_fn(width, height, |x, y| {
+                    let p = pixel(x, y);
+                    image::Rgb(if self.format == RawFormat::Rgb888 {
+                        [p[0], p[1], p[2]]
+                    } else {
+                        [p[2], p[1], p[0]]
+                    })
+                })),
+            RawFormat::Rgba8888 | RawFormat::Bgra8888 => DynamicImage::ImageRgba8(
+                RgbaImage::from_fn(width, height, |x, y| {
+                    let p = pixel(x, y);
+                    image::Rgba(if self.format == RawFormat::Rgba8888 {
+                        [p[0], p[1], p[2], p[3]]
+                    } else {
+                        [p[2], p[1], p[0], p[3]]
+                    })
+                })),
+            RawFormat::Yuv420 => {
+                let chroma_stride = stride.div_ceil(2);
+                let chroma_len = chroma_stride * (height as usize).div_ceil(2);
+                let (u, v) = data[luma_len..].split_at(chroma_len);
+                DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+                    let idx = (y >> 1) as usize * chroma_stride + (x >> 1) as usize;
+                    image::Rgb(yuv_to_rgb(pixel(x, y)[0], u[idx], v[idx]))
+                }))
+            },
+            RawFormat::Nv12 | RawFormat::Nv21 => {
+                let uv = &data[luma_len..];
+                DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+                    let idx = (y >> 1) as usize * stride + (x & !1) as usize;
+                    let [mut u, mut v] = [uv[idx], uv[idx + 1]];
+                    if self.format == RawFormat::Nv21 {
+                        (u, v) = (v, u);
+                    }
+                    image::Rgb(yuv_to_rgb(pixel(x, y)[0], u, v))
+                }))
+            },
+        })
+    }
+}
+
+#[test]
+fn raw_decode_test() {
+    let decode = |layout: &str, data: &[u8]| {
+        layout.parse::<RawLayout>().unwrap().decode(data)
+    };
+    // 每行末尾有一个字节的填充, 并跳过开头的两个字节
+    let img = decode("2x2:rgb565:5:2", &[
+        9, 9,
+        0x00, 0xf8, 0xe0, 0x07, 0,
+        0x1f, 0x00, 0xff, 0xff, 0,
+    ]).unwrap().into_rgb8();
+    assert_eq!(img.get_pixel(0, 0).0, [255, 0, 0]);
+    assert_eq!(img.get_pixel(1, 0).0, [0, 255, 0]);
+    assert_eq!(img.get_pixel(0, 1).0, [0, 0, 255]);
+    assert_eq!(img.get_pixel(1, 1).0, [255; 3]);
+
+    let img = decode("1x1:bgra", &[1, 2, 3, 4]).unwrap().into_rgba8();
+    assert_eq!(img.get_pixel(0, 0).0, [3, 2, 1, 4]);
+
+    let img = decode("1x1:gray16", &[0x34, 0x12]).unwrap().into_luma16();
+    assert_eq!(img.get_pixel(0, 0).0, [0x1234]);
+
+    // 2x2 的 Y 平面之后为 U V 各一个字节
+    let yuv = decode("2x2:yuv420", &[81, 81, 81, 235, 90, 240]).unwrap().into_rgb8();
+    let nv12 = decode("2x2:nv12", &[81, 81, 81, 235, 90, 240]).unwrap().into_rgb8();
+    assert_eq!(yuv, nv12);
+    assert_eq!(yuv.get_pixel(0, 0).0, [254, 0, 0]);
+    let nv21 = decode("2x2:nv21", &[81, 81, 81, 235, 240, 90]).unwrap().into_rgb8();
+    assert_eq!(nv21, nv12);
+
+    assert_eq!(decode("2x2:nv12", &[0; 5]), Err(RawError::TooShort(6, 5)));
+
+    // 行宽不足一行时报错, 而不是在解码时越界
+    let layout = RawLayout {
+        width: 4, height: 2, format: RawFormat::Rgb888, stride: Some(2), offset: 0,
+    };
+    assert_eq!(layout.decode(&[0; 64]), Err(RawError::Stride(2, 12)));
+    assert_eq!("4x2:rgb:2".parse::<RawLayout>(), Err(RawError::Stride(2, 12)));
+    assert_eq!("3x4:nv12".parse::<RawLayout>(), Err(RawError::Stride(3, 4)));
+    assert!("3x4:nv12:4".parse::<RawLayout>().is_ok());
+    assert!("3x4:yuv420".parse::<RawLayout>().is_ok());
+    let layout = RawLayout {
+        width: u32::MAX, height: u32::MAX, format: RawFormat::Rgba8888,
+        stride: Some(usize::MAX / 2), offset: 0,
+    };
+    assert_eq!(layout.data_len(), Err(RawError::TooLarge));
+}
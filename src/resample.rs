@@ -0,0 +1,378 @@
+use std::{
+    f32::consts::PI,
+    num::NonZeroUsize,
+    sync::OnceLock,
+    thread,
+};
+
+use image::{
+    imageops::FilterType,
+    DynamicImage,
+    ImageBuffer,
+    Pixel,
+};
+
+use super::*;
+
+/// 每个线程至少处理的行数, 以免小图片的线程开销超过收益
+const MIN_BAND_ROWS: usize = 16;
+
+/// 可用的线程数, 仅在首次调用时查询
+pub fn available_threads() -> usize {
+    static THREADS: OnceLock<usize> = OnceLock::new();
+    *THREADS.get_or_init(|| {
+        thread::available_parallelism().map_or(1, NonZeroUsize::get)
+    })
+}
+
+/// Run `f` on bands of rows of `data` on up to `threads` threads.
+///
+/// `f` is given the index of the first row of the band and the band,
+/// each row has `row_len` elements.
+/// # Examples
+/// ```
+/// use timg::par_bands;
+/// let mut data = vec![0; 3 * 100];
+/// par_bands(&mut data, 3, 4, |first, band| {
+///     for (i, row) in band.chunks_mut(3).enumerate() {
+///         row.fill(first + i);
+///     }
+/// });
+/// assert_eq!(data[3 * 42..3 * 43], [42; 3]);
+/// ```
+pub fn par_bands<T, F>(data: &mut [T], row_len: usize, threads: usize, f: F)
+where T: Send,
+      F: Fn(usize, &mut [T]) + Sync,
+{
+    let rows = data.len() / row_len.max(1);
+    let band_rows = rows.div_ceil(threads.max(1)).max(MIN_BAND_ROWS);
+    if band_rows >= rows {
+        return f(0, data);
+    }
+    thread::scope(|scope| {
+        for (i, band) in data.chunks_mut(band_rows * row_len).enumerate() {
+            let f = &f;
+            scope.spawn(move || f(i * band_rows, band));
+        }
+    });
+}
+
+/// [`par_bands`] over the rows of an image.
+///
+/// `f` is given the y of the first row of the band
+/// and the band as an image borrowing these rows.
+/// # Examples
+/// ```
+/// use image::{GrayImage, Luma};
+/// use timg::par_rows;
+/// let mut img = GrayImage::new(3, 100);
+/// par_rows(&mut img, 4, |first, band| {
+///     for (_, y, pixel) in band.enumerate_pixels_mut() {
+///         *pixel = Luma([(first + y) as u8]);
+///     }
+/// });
+/// assert_eq!(img.get_pixel(2, 42).0, [42]);
+/// ```
+pub fn par_rows<P, F>(img: &mut ImageBuffer<P, Vec<P::Subpixel>>, threads: usize, f: F)
+where P: Pixel,
+      P::Subpixel: Send,
+      F: Fn(SizeType, &mut ImageBuffer<P, &mut [P::Subpixel]>) + Sync,
+{
+    let width = img.width();
+    let row_len = width as usize * P::CHANNEL_COUNT as usize;
+    par_bands(img, row_len, threads, |first, band| {
+        let rows = band.len() / row_len.max(1);
+        let mut band = ImageBuffer::from_raw(width, rows as SizeType, band)
+            .expect("band of whole rows");
+        f(first as SizeType, &mut band);
+    });
+}
+
+fn sinc(t: f32) -> f32 {
+    if t == 0.0 {
+        1.0
+    } else {
+        (t * PI).sin() / (t * PI)
+    }
+}
+
+/// 滤波器的核函数与支持半径, 与 [`image::imageops::resize`] 中的一致
+fn filter_kernel(filter: FilterType) -> (fn(f32) -> f32, f32) {
+    match filter {
+        FilterType::Nearest => (|_| 1.0, 0.0),
+        FilterType::Triangle => (|x| (1.0 - x.abs()).max(0.0), 1.0),
+        FilterType::CatmullRom => (|x| {
+            let a = x.abs();
+            let k = if a < 1.0 {
+                9.0 * a.powi(3) - 15.0 * a.powi(2) + 6.0
+            } else if a < 2.0 {
+                -3.0 * a.powi(3) + 15.0 * a.powi(2) - 24.0 * a + 12.0
+            } else {
+                0.0
+            };
+            k / 6.0
+        }, 2.0),
+        FilterType::Gaussian => (|x| {
+            const R: f32 = 0.5;
+            ((2.0 * PI).sqrt() * R).recip() * (-x.powi(2) / (2.0 * R.powi(2))).exp()
+        }, 3.0),
+        FilterType::Lanczos3 => (|x| {
+            if x.abs() < 3.0 { sinc(x) * sinc(x / 3.0) } else { 0.0 }
+        }, 3.0),
+    }
+}
+
+/// 一维缩放时, 每个输出像素对应的首个输入像素, 及归一化的权重
+fn sample_weights(len: u32,
+                  new_len: u32,
+                  filter: FilterType) -> Vec<(usize, Vec<f32>)> {
+    let (kernel, support) = filter_kernel(filter);
+    let ratio = len as f32 / new_len as f32;
+    let sratio = ratio.max(1.0);
+    let src_support = support * sratio;
+    (0..new_len).map(|out| {
+        let input = (out as f32 + 0.5) * ratio;
+        let left = ((input - src_support).floor() as i64)
+            .clamp(0, len as i64 - 1);
+        let right = ((input + src_support).ceil() as i64)
+            .clamp(left + 1, len as i64);
+        // 核函数以像素中心为 0
+        let input = input - 0.5;
+        let mut weights = (left..right)
+            .map(|i| kernel((i as f32 - input) / sratio))
+            .collect::<Vec<_>>();
+        let sum: f32 = weights.iter().sum();
+        weights.iter_mut().for_each(|w| *w /= sum);
+        (left as usize, weights)
+    }).collect()
+}
+
+/// 可以重新采样的通道值类型
+pub trait Sample: Copy + Into<f32> + Send + Sync {
+    /// 表示完全不透明或最亮的值
+    const MAX: f32;
+    /// 从采样结果转换, 整数会被取整并限制在范围内
+    fn from_f32(value: f32) -> Self;
+}
+impl Sample for u8 {
+    const MAX: f32 = u8::MAX as f32;
+    fn from_f32(value: f32) -> Self {
+        value.round().clamp(0.0, u8::MAX as f32) as u8
+    }
+}
+impl Sample for u16 {
+    const MAX: f32 = u16::MAX as f32;
+    fn from_f32(value: f32) -> Self {
+        value.round().clamp(0.0, u16::MAX as f32) as u16
+    }
+}
+impl Sample for f32 {
+    const MAX: f32 = 1.0;
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+}
+
+/// Resample pixels of `channels` channels in row bands on multiple threads.
+///
+/// The same separable filters as [`image::imageops::resize`]
+/// (vertical first, then horizontal), but the values are not clamped.
+/// Returns the values as `f32`, in the range of the input.
+pub fn resample<S>(pixels: &[S],
+                   channels: usize,
+                   [width, height]: [SizeType; 2],
+                   [new_width, new_height]: [SizeType; 2],
+                   filter: FilterType,
+                   threads: usize) -> Vec<f32>
+where S: Copy + Into<f32> + Sync,
+{
+    let row_len = width as usize * channels;
+    if [width, height] == [new_width, new_height] {
+        return pixels.iter().map(|&x| x.into()).collect();
+    }
+    if width == 0 || height == 0 {
+        // 没有可以采样的像素, 例如视区在图片之外
+        return vec![0.0; new_width as usize * new_height as usize * channels];
+    }
+    let weights = sample_weights(height, new_height, filter);
+    let mut vertical = vec![0.0; row_len * new_height as usize];
+    par_bands(&mut vertical, row_len, threads, |first, band| {
+        for (y, row) in band.chunks_mut(row_len).enumerate() {
+            let (left, weights) = &weights[first + y];
+            for (i, &w) in weights.iter().enumerate() {
+                let src = &pixels[(left + i) * row_len..][..row_len];
+                for (out, &x) in row.iter_mut().zip(src) {
+                    *out += x.into() * w;
+                }
+            }
+        }
+    });
+    let weights = sample_weights(width, new_width, filter);
+    let new_row_len = new_width as usize * channels;
+    let mut res = vec![0.0; new_row_len * new_height as usize];
+    par_bands(&mut res, new_row_len, threads, |first, band| {
+        for (y, row) in band.chunks_mut(new_row_len).enumerate() {
+            let src = &vertical[(first + y) * row_len..][..row_len];
+            for (out, (left, weights)) in row.chunks_mut(channels).zip(&weights) {
+                for (i, &w) in weights.iter().enumerate() {
+                    let pixel = &src[(left + i) * channels..][..channels];
+                    for (out, &x) in out.iter_mut().zip(pixel) {
+                        *out += x * w;
+                    }
+                }
+            }
+        }
+    });
+    res
+}
+
+/// Like [`resample`], but only the output pixels selected by `select`
+/// are computed, the others are zero.
+///
+/// The input pixels are read by `source` when needed,
+/// so the cost is about the selected part of a full resample.
+pub fn resample_where<const N: usize, F, G>([width, height]: [SizeType; 2],
+                                            [new_width, new_height]: [SizeType; 2],
+                                            filter: FilterType,
+                                            threads: usize,
+                                            source: F,
+                                            select: G) -> Vec<[f32; N]>
+where F: Fn(SizeType, SizeType) -> [f32; N] + Sync,
+      G: Fn(SizeType, SizeType) -> bool + Sync,
+{
+    let mut res = vec![[0.0; N]; new_width as usize * new_height as usize];
+    if res.is_empty() || width == 0 || height == 0 {
+        return res;
+    }
+    let v_weights = sample_weights(height, new_height, filter);
+    let h_weights = sample_weights(width, new_width, filter);
+    par_bands(&mut res, new_width as usize, threads, |first, band| {
+        // 当前输出行中纵向采样后的各列, 仅在需要时计算
+        let mut columns: Vec<Option<[f32; N]>> = vec![None; width as usize];
+        for (y, row) in band.chunks_mut(new_width as usize).enumerate() {
+            let y = first + y;
+            let (top, v_weights) = &v_weights[y];
+            columns.fill(None);
+            for (x, (out, (left, weights))) in row.iter_mut().zip(&h_weights).enumerate() {
+                if ! select(x as SizeType, y as SizeType) {
+                    continue;
+                }
+                for (i, &w) in weights.iter().enumerate() {
+                    let sx = left + i;
+                    let column = *columns[sx].get_or_insert_with(|| {
+                        let mut sum = [0.0; N];
+                        for (j, &vw) in v_weights.iter().enumerate() {
+                            let pixel = source(sx as SizeType, (top + j) as SizeType);
+                            for (sum, x) in sum.iter_mut().zip(pixel) {
+                                *sum += x * vw;
+                            }
+                        }
+                        sum
+                    });
+                    for (out, x) in out.iter_mut().zip(column) {
+                        *out += x * w;
+                    }
+                }
+            }
+        }
+    });
+    res
+}
+
+/// 使用多个线程缩放图片, 保留像素类型, 浮点值不会被限制在 0 到 1
+pub fn resize_buffer<P>(img: &ImageBuffer<P, Vec<P::Subpixel>>,
+                        width: SizeType,
+                        height: SizeType,
+                        filter: FilterType,
+                        threads: usize) -> ImageBuffer<P, Vec<P::Subpixel>>
+where P: Pixel,
+      P::Subpixel: Sample,
+{
+    let res = resample(img.as_raw(),
+                       P::CHANNEL_COUNT as usize,
+                       [img.width(), img.height()],
+                       [width, height],
+                       filter,
+                       threads);
+    let res = res.into_iter().map(P::Subpixel::from_f32).collect();
+    ImageBuffer::from_raw(width, height, res).unwrap()
+}
+
+/// Resize on multiple threads, like [`DynamicImage::resize_exact`].
+///
+/// The color type is kept, except that float values are not clamped.
+/// # Examples
+/// ```
+/// # use image::{imageops::FilterType, DynamicImage, RgbImage};
+/// use timg::resize_parallel;
+/// let img = DynamicImage::ImageRgb8(RgbImage::from_fn(64, 48, |x, y| {
+///     image::Rgb([(x * 4) as u8, (y * 5) as u8, 128])
+/// }));
+/// for filter in [FilterType::Nearest, FilterType::Lanczos3] {
+///     let expected = img.resize_exact(20, 30, filter).into_rgb8();
+///     let res = resize_parallel(&img, 20, 30, filter, 4).into_rgb8();
+///     assert_eq!(res.dimensions(), (20, 30));
+///     // 中间结果未被取整, 因此可能相差 1
+///     assert!(res.pixels().zip(expected.pixels()).all(|(a, b)| {
+///         a.0.iter().zip(b.0).all(|(&a, b)| a.abs_diff(b) <= 1)
+///     }));
+/// }
+/// ```
+pub fn resize_parallel(img: &DynamicImage,
+                       width: SizeType,
+                       height: SizeType,
+                       filter: FilterType,
+                       threads: usize) -> DynamicImage {
+    macro_rules! resize {
+        ( $( $variant:ident ),* ) => {
+            match img {
+                $(
+                    DynamicImage::$variant(img) => DynamicImage::$variant(
+                        resize_buffer(img, width, height, filter, threads)),
+                )*
+                img => DynamicImage::ImageRgba32F(
+                    resize_buffer(&img.to_rgba32f(), width, height, filter, threads)),
+            }
+        };
+    }
+    resize!(ImageLuma8, ImageLumaA8, ImageRgb8, ImageRgba8,
+            ImageLuma16, ImageLumaA16, ImageRgb16, ImageRgba16,
+            ImageRgb32F, ImageRgba32F)
+}
+
+#[test]
+fn resample_test() {
+    use image::Rgba32FImage;
+
+    // 与单线程的结果一致, 且浮点值不被限制
+    let img = Rgba32FImage::from_fn(37, 101, |x, y| {
+        image::Rgba([x as f32 / 10.0, y as f32 / 50.0, 0.5, 1.0])
+    });
+    for filter in [FilterType::Nearest, FilterType::Triangle,
+                   FilterType::CatmullRom, FilterType::Gaussian,
+                   FilterType::Lanczos3] {
+        let single = resize_buffer(&img, 13, 90, filter, 1);
+        let multi = resize_buffer(&img, 13, 90, filter, 7);
+        assert_eq!(single, multi, "{:?}", filter);
+        assert!(single.pixels().any(|x| x[0] > 1.0));
+    }
+    let res = resize_buffer(&img, 37, 101, FilterType::Lanczos3, 4);
+    assert_eq!(res, img);
+    let empty = Rgba32FImage::new(0, 5);
+    let res = resize_buffer(&empty, 3, 2, FilterType::Triangle, 4);
+    assert_eq!(res, Rgba32FImage::new(3, 2));
+
+    // 仅计算选中的像素, 结果与完整的缩放一致
+    let select = |x: SizeType, y: SizeType| (x + y).is_multiple_of(3);
+    for filter in [FilterType::Nearest, FilterType::Lanczos3] {
+        let full = resize_buffer(&img, 13, 90, filter, 1);
+        let part = resample_where([37, 101], [13, 90], filter, 4,
+                                  |x, y| img.get_pixel(x, y).0, select);
+        for (i, (pixel, part)) in full.pixels().zip(&part).enumerate() {
+            let [x, y] = [i as SizeType % 13, i as SizeType / 13];
+            let expected = if select(x, y) { pixel.0 } else { [0.0; 4] };
+            assert!(expected.iter().zip(part).all(|(a, b)| (a - b).abs() < 1e-5),
+                    "{:?} {:?} {:?}", filter, expected, part);
+        }
+    }
+}
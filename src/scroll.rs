@@ -0,0 +1,103 @@
+use term_lattice::{
+    Color,
+    ScreenBuffer,
+};
+
+/// Control sequence scrolling the top `height` text lines by `rows`.
+///
+/// Positive `rows` move the content up (`CSI S`), negative down (`CSI T`).
+/// The status line below the region is kept, the colors are reset first
+/// so that the exposed lines are cleared to the default background.
+/// # Examples
+/// ```
+/// use timg::scroll_region;
+/// assert_eq!(scroll_region(2, 20), "\x1b[0m\x1b[1;20r\x1b[2S\x1b[r");
+/// assert_eq!(scroll_region(-3, 20), "\x1b[0m\x1b[1;20r\x1b[3T\x1b[r");
+/// ```
+pub fn scroll_region(rows: i32, height: u32) -> String {
+    format!("\x1b[0m\x1b[1;{}r\x1b[{}{}\x1b[r",
+            height,
+            rows.unsigned_abs(),
+            if rows > 0 { 'S' } else { 'T' })
+}
+
+/// 屏幕内容滚动 `rows` 个文本行后, 屏幕上的颜色, 露出的行为 `None`
+fn scrolled_bg_colors(buf: &ScreenBuffer, rows: i32) -> impl Iterator<Item = Color> + '_ {
+    let width = buf.size()[0] as isize;
+    let shift = rows as isize * 2 * width;
+    let bgs = buf.get_bg_colors_borrow();
+    let len = bgs.len() as isize;
+    let colors = (0..len).map(move |idx| {
+        let src = idx + shift;
+        if (0..len).contains(&src) { bgs[src as usize] } else { Color::None }
+    });
+    colors.collect::<Vec<_>>().into_iter()
+}
+
+/// 将要输出的颜色中, 与滚动 `rows` 个文本行后的屏幕内容相似的像素数
+///
+/// opt 为 0 时仍计入完全相同的像素
+pub fn scroll_matches(buf: &ScreenBuffer, rows: i32) -> usize {
+    let level = buf.cfg.chromatic_aberration.max(1);
+    let colors = buf.get_colors_borrow();
+    scrolled_bg_colors(buf, rows)
+        .zip(colors.iter())
+        .filter(|(bg, color)| {
+            ! matches!(bg, Color::None)
+                && color.chromatic_aberration(bg) < level
+        })
+        .count()
+}
+
+/// Make the model of the screen content match the terminal
+/// after [`scroll_region`] by `rows`.
+///
+/// The model can only be written by flushing, so the scrolled content
+/// is flushed with no tolerance, and the output is dropped.
+/// The colors to output are kept.
+pub fn scroll_model(buf: &mut ScreenBuffer, rows: i32) {
+    let colors = buf.get_colors_borrow().clone();
+    let scrolled = scrolled_bg_colors(buf, rows).collect::<Vec<_>>();
+    for (idx, color) in scrolled.into_iter().enumerate() {
+        buf.set_idx(idx, color);
+    }
+    let opt = buf.cfg.chromatic_aberration;
+    buf.cfg.chromatic_aberration = 0;
+    buf.init_bg_colors();
+    buf.flush(false);
+    buf.cfg.chromatic_aberration = opt;
+    for (idx, color) in colors.into_iter().enumerate() {
+        buf.set_idx(idx, color);
+    }
+}
+
+#[test]
+fn scroll_model_test() {
+    let mut buf = ScreenBuffer::new([3, 6]);
+    buf.cfg.chromatic_aberration = 10;
+    let color = |y: u32| Color::Rgb([y as u8 * 40, 0, 0]);
+    for y in 0..6 {
+        for x in 0..3 {
+            buf.set([x, y], color(y));
+        }
+    }
+    buf.flush(false);
+    // 图片向下移动了一个文本行, 屏幕内容向上滚动
+    for y in 0..6 {
+        for x in 0..3 {
+            buf.set([x, y], color((y + 2).min(5)));
+        }
+    }
+    assert_eq!(scroll_matches(&buf, 0), 3);
+    assert_eq!(scroll_matches(&buf, 1), 12);
+    scroll_model(&mut buf, 1);
+    let bgs = buf.get_bg_colors_borrow().clone();
+    assert_eq!(bgs[..12], (2..6).flat_map(|y| [color(y); 3]).collect::<Vec<_>>());
+    assert_eq!(bgs[12..], [Color::None; 6]);
+    // 要输出的颜色未改变
+    assert_eq!(buf.get([0, 5]), color(5));
+    assert_eq!(buf.cfg.chromatic_aberration, 10);
+    // 只有露出的行需要输出
+    let out = buf.flush(false);
+    assert_eq!(out.matches('▄').count(), 3);
+}
@@ -0,0 +1,111 @@
+use std::time::{
+    Duration,
+    Instant,
+};
+
+/// 可以选择的切换间隔, 以毫秒计
+pub const SLIDESHOW_INTERVALS: &[u64] = &[
+    500, 1000, 2000, 3000, 5000, 10000, 15000, 30000, 60000
+];
+pub const DEFAULT_SLIDESHOW_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Timer of the slideshow, advancing to the next image every interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slideshow {
+    interval: Duration,
+    /// 播放时下一次切换的时间
+    next: Option<Instant>,
+}
+impl Slideshow {
+    /// 创建一个暂停的幻灯片
+    pub fn new(interval: Duration) -> Self {
+        Self { interval, next: None }
+    }
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+    pub fn is_playing(&self) -> bool {
+        self.next.is_some()
+    }
+    /// 下一次切换的时间, 暂停时为 `None`
+    pub fn deadline(&self) -> Option<Instant> {
+        self.next
+    }
+    pub fn play(&mut self) {
+        self.next = Some(Instant::now() + self.interval);
+    }
+    pub fn pause(&mut self) {
+        self.next = None;
+    }
+    pub fn toggle(&mut self) {
+        if self.is_playing() {
+            self.pause()
+        } else {
+            self.play()
+        }
+    }
+    /// 切换了图片后, 从现在开始重新计时
+    pub fn restart(&mut self) {
+        if self.is_playing() {
+            self.play()
+        }
+    }
+    /// 在 [`SLIDESHOW_INTERVALS`] 中选择更长或更短的间隔, 并重新计时
+    /// # Examples
+    /// ```
+    /// # use std::time::Duration;
+    /// use timg::Slideshow;
+    /// let mut slideshow = Slideshow::new(Duration::from_millis(4000));
+    /// slideshow.step_interval(1);
+    /// assert_eq!(slideshow.interval(), Duration::from_secs(5));
+    /// slideshow.step_interval(-2);
+    /// assert_eq!(slideshow.interval(), Duration::from_secs(2));
+    /// slideshow.step_interval(-9);
+    /// assert_eq!(slideshow.interval(), Duration::from_millis(500));
+    /// ```
+    pub fn step_interval(&mut self, step: isize) {
+        let ms = self.interval.as_millis() as u64;
+        let idx = if step > 0 {
+            SLIDESHOW_INTERVALS.iter().position(|&x| x > ms)
+                .map(|i| i + step as usize - 1)
+        } else {
+            SLIDESHOW_INTERVALS.iter().rposition(|&x| x < ms)
+                .map(|i| i.saturating_sub((-step) as usize - 1))
+        };
+        let ms = match idx {
+            Some(idx) => SLIDESHOW_INTERVALS[idx.min(SLIDESHOW_INTERVALS.len() - 1)],
+            None => ms, // 已经是最长或最短的间隔
+        };
+        self.interval = Duration::from_millis(ms);
+        self.restart();
+    }
+    pub fn fmt_status(&self) -> String {
+        format!("Show[{}s{}] ",
+                self.interval.as_secs_f64(),
+                if self.is_playing() { "" } else { " Paused" })
+    }
+}
+
+#[test]
+fn slideshow_test() {
+    let mut slideshow = Slideshow::new(Duration::from_secs(2));
+    assert!(! slideshow.is_playing());
+    assert_eq!(slideshow.deadline(), None);
+    slideshow.restart();
+    assert_eq!(slideshow.deadline(), None);
+    assert_eq!(slideshow.fmt_status(), "Show[2s Paused] ");
+    slideshow.toggle();
+    let deadline = slideshow.deadline().unwrap();
+    assert!(deadline > Instant::now() + Duration::from_secs(1));
+    slideshow.step_interval(-1);
+    assert!(slideshow.deadline().unwrap() < deadline);
+    assert_eq!(slideshow.fmt_status(), "Show[1s] ");
+    slideshow.step_interval(-1);
+    slideshow.step_interval(-1);
+    assert_eq!(slideshow.interval(), Duration::from_millis(500));
+    assert_eq!(slideshow.fmt_status(), "Show[0.5s] ");
+    slideshow.step_interval(20);
+    assert_eq!(slideshow.interval(), Duration::from_secs(60));
+    slideshow.toggle();
+    assert!(! slideshow.is_playing());
+}
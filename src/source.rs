@@ -0,0 +1,277 @@
+use std::{
+    error::Error,
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use image::{
+    DynamicImage,
+    ImageFormat,
+};
+
+use super::*;
+
+/// How image files are decoded, kept for reloading.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageLoader {
+    /// 将文件作为原始像素读取
+    pub raw_layout: Option<RawLayout>,
+    pub npy_range: NpyRange,
+}
+impl ImageLoader {
+    pub fn new() -> Self {
+        Self { raw_layout: None, npy_range: NpyRange::Auto }
+    }
+    fn is_npy(path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("npy"))
+    }
+    /// 是否可能是能够读取的文件, 仅根据扩展名判断
+    /// # Examples
+    /// ```
+    /// use std::path::Path;
+    /// use timg::ImageLoader;
+    /// let loader = ImageLoader::new();
+    /// assert!(loader.can_load(Path::new("a.png")));
+    /// assert!(loader.can_load(Path::new("b.NPY")));
+    /// assert!(! loader.can_load(Path::new("c.txt")));
+    /// ```
+    pub fn can_load(&self, path: &Path) -> bool {
+        self.raw_layout.is_some()
+            || Self::is_npy(path)
+            || ImageFormat::from_path(path).is_ok()
+    }
+    /// 读取图片, 除图片文件外也可以是原始像素或 `.npy` 数组
+    pub fn load(&self, path: &Path) -> Result<DynamicImage, Box<dyn Error>> {
+        Ok(if let Some(layout) = self.raw_layout {
+            layout.decode(&fs::read(path)?)?
+        } else if Self::is_npy(path) {
+            NpyArray::read(&fs::read(path)?)?.to_image(self.npy_range)
+        } else {
+            image::open(path)?
+        })
+    }
+}
+impl Default for ImageLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SourceKind {
+    /// 一个或多个文件, 可选地监视当前文件的修改
+    Files {
+        paths: Vec<PathBuf>,
+        idx: usize,
+        /// 当前的文件
+        watcher: FileWatcher,
+        watch: bool,
+    },
+    /// 目录中最新的文件
+    Dir(DirWatcher),
+}
+
+/// Where the viewed image comes from, and whether it should be reloaded.
+///
+/// While paused, changes are remembered but not reported,
+/// so the view does not jump while inspecting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageSource {
+    loader: ImageLoader,
+    kind: SourceKind,
+    paused: bool,
+    /// 暂停时发生了改变
+    pending: bool,
+    /// 最后一次读取的文件, 即正在显示的图片
+    shown: Option<PathBuf>,
+}
+impl ImageSource {
+    fn new(loader: ImageLoader, kind: SourceKind) -> Self {
+        Self { loader, kind, paused: false, pending: false, shown: None }
+    }
+    pub fn file<P: AsRef<Path>>(path: P, loader: ImageLoader, watch: bool) -> Self {
+        Self::files(vec![path.as_ref().to_path_buf()], loader, watch)
+    }
+    /// 依次显示的多个文件, 从第一个开始
+    ///
+    /// # Panics
+    /// `paths` 为空时
+    pub fn files(paths: Vec<PathBuf>, loader: ImageLoader, watch: bool) -> Self {
+        let watcher = FileWatcher::new(&paths[0]);
+        Self::new(loader, SourceKind::Files { paths, idx: 0, watcher, watch })
+    }
+    /// 显示目录中最新的图片, 还没有图片时 [`Self::path`] 为 `None`
+    pub fn dir<P: AsRef<Path>>(dir: P, loader: ImageLoader) -> Self {
+        let mut watcher = DirWatcher::new(dir);
+        watcher.changed(|path| loader.can_load(path));
+        Self::new(loader, SourceKind::Dir(watcher))
+    }
+    pub fn loader(&self) -> &ImageLoader {
+        &self.loader
+    }
+    /// 当前图片的路径
+    pub fn path(&self) -> Option<&Path> {
+        match &self.kind {
+            SourceKind::Files { watcher, .. } => Some(watcher.path()),
+            SourceKind::Dir(watcher) => watcher.newest(),
+        }
+    }
+    /// 最后一次读取的文件, 即正在显示的图片
+    pub fn shown(&self) -> Option<&Path> {
+        self.shown.as_deref()
+    }
+    /// 文件的数量与当前文件的下标, 目录模式中为 `None`
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match &self.kind {
+            SourceKind::Files { paths, idx, .. } => Some((paths.len(), *idx)),
+            SourceKind::Dir(_) => None,
+        }
+    }
+    /// 循环地向前或向后切换文件, 返回当前文件是否改变
+    /// # Examples
+    /// ```
+    /// use timg::{ImageLoader, ImageSource};
+    /// let paths = vec!["a.png".into(), "b.png".into(), "c.png".into()];
+    /// let mut source = ImageSource::files(paths, ImageLoader::new(), false);
+    /// assert!(source.step(-1));
+    /// assert_eq!(source.position(), Some((3, 2)));
+    /// assert!(source.step(2));
+    /// assert_eq!(source.path().unwrap().to_str(), Some("b.png"));
+    /// assert!(! source.step(3));
+    /// ```
+    pub fn step(&mut self, step: isize) -> bool {
+        let SourceKind::Files { paths, idx, watcher, .. } = &mut self.kind
+            else { return false };
+        let len = paths.len() as isize;
+        let new_idx = (*idx as isize + step).rem_euclid(len) as usize;
+        if new_idx == *idx {
+            return false;
+        }
+        *idx = new_idx;
+        *watcher = FileWatcher::new(&paths[new_idx]);
+        true
+    }
+    /// 目录模式中被监视的目录
+    pub fn dir_path(&self) -> Option<&Path> {
+        match &self.kind {
+            SourceKind::Dir(watcher) => Some(watcher.dir()),
+            SourceKind::Files { .. } => None,
+        }
+    }
+    /// 读取当前的图片, 还没有图片时返回 `None`
+    pub fn load(&mut self) -> Option<Result<DynamicImage, Box<dyn Error>>> {
+        let path = self.path()?.to_path_buf();
+        let img = self.loader.load(&path);
+        self.shown = Some(path);
+        Some(img)
+    }
+    /// 是否需要定时调用 [`Self::changed`]
+    pub fn is_watching(&self) -> bool {
+        match self.kind {
+            SourceKind::Files { watch, .. } => watch,
+            SourceKind::Dir(_) => true,
+        }
+    }
+    /// 是否应该重新读取图片, 暂停时总是为假
+    pub fn changed(&mut self) -> bool {
+        let loader = self.loader;
+        let changed = match &mut self.kind {
+            SourceKind::Files { watcher, watch, .. }
+                => *watch && watcher.changed(),
+            SourceKind::Dir(watcher)
+                => watcher.changed(|path| loader.can_load(path)),
+        };
+        if self.paused {
+            self.pending |= changed;
+            false
+        } else {
+            changed
+        }
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    /// 暂停时是否有未显示的改变
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+    /// 切换暂停, 恢复时返回暂停期间是否有改变
+    pub fn toggle_pause(&mut self) -> bool {
+        self.paused = ! self.paused;
+        let pending = self.pending;
+        self.pending = false;
+        ! self.paused && pending
+    }
+    /// 用于状态行的简短描述, 未暂停的单个文件时为空
+    pub fn fmt_status(&self) -> String {
+        let name = match self.shown.as_deref() {
+            Some(path) => path.file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default(),
+            // 目录中还没有图片
+            None => "Waiting".into(),
+        };
+        let pause = match (self.paused, self.pending) {
+            (false, _) => "",
+            (true, false) => " Paused",
+            (true, true) => " Paused*",
+        };
+        match &self.kind {
+            SourceKind::Dir(_) => format!("Dir[{}{}] ", name, pause),
+            SourceKind::Files { paths, idx, .. } if paths.len() > 1
+                => format!("File[{} {}/{}{}] ", name, idx + 1, paths.len(), pause),
+            SourceKind::Files { .. } if self.paused
+                => format!("Watch[{}] ", &pause[1..]),
+            SourceKind::Files { .. } => String::new(),
+        }
+    }
+}
+
+#[test]
+fn image_source_test() {
+    let dir: PathBuf = std::env::temp_dir()
+        .join(format!("timg-source-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let mut source = ImageSource::dir(&dir, ImageLoader::new());
+    assert!(source.path().is_none());
+    assert!(source.load().is_none());
+    assert!(source.is_watching());
+    assert_eq!(source.fmt_status(), "Dir[Waiting] ");
+    fs::write(dir.join("a.png"), b"").unwrap();
+    assert!(source.changed());
+    assert_eq!(source.path(), Some(&*dir.join("a.png")));
+    assert!(source.load().unwrap().is_err());
+    assert_eq!(source.fmt_status(), "Dir[a.png] ");
+
+    assert!(! source.toggle_pause());
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(dir.join("b.png"), b"").unwrap();
+    assert!(! source.changed());
+    assert!(source.is_pending());
+    assert_eq!(source.fmt_status(), "Dir[a.png Paused*] ");
+    assert!(source.toggle_pause());
+    assert_eq!(source.path(), Some(&*dir.join("b.png")));
+    assert!(! source.is_pending());
+
+    let mut source = ImageSource::file(dir.join("a.png"), ImageLoader::new(), false);
+    fs::write(dir.join("a.png"), b"a").unwrap();
+    assert!(! source.is_watching());
+    assert!(! source.changed());
+    assert_eq!(source.fmt_status(), "");
+    assert!(! source.step(1));
+    assert_eq!(source.position(), Some((1, 0)));
+
+    let paths = vec![dir.join("a.png"), dir.join("b.png")];
+    let mut source = ImageSource::files(paths, ImageLoader::new(), true);
+    assert!(source.load().unwrap().is_err());
+    assert!(source.step(1));
+    assert_eq!(source.shown(), Some(&*dir.join("a.png")));
+    assert_eq!(source.fmt_status(), "File[a.png 2/2] ");
+    assert!(source.load().unwrap().is_err());
+    assert_eq!(source.fmt_status(), "File[b.png 2/2] ");
+    fs::remove_dir_all(&dir).unwrap();
+}
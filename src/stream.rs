@@ -0,0 +1,356 @@
+use std::io::{
+    self,
+    BufRead,
+    BufReader,
+    ErrorKind,
+    Read,
+};
+
+use image::{
+    DynamicImage,
+    GrayImage,
+    ImageBuffer,
+    ImageFormat,
+    RgbImage,
+};
+
+use super::*;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// 由头部中的大小得到的一帧的最大字节数, 以免错误的头部导致分配过多的内存
+const MAX_FRAME_LEN: usize = 1 << 30;
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// 由头部中的大小得到一帧的字节数, 溢出或超过 [`MAX_FRAME_LEN`] 时返回错误
+fn frame_len(width: SizeType,
+             height: SizeType,
+             bytes_per_pixel: usize) -> io::Result<usize> {
+    (width as usize).checked_mul(height as usize)
+        .and_then(|len| len.checked_mul(bytes_per_pixel))
+        .filter(|&len| len <= MAX_FRAME_LEN)
+        .ok_or_else(|| invalid_data(format!("frame too large: {}x{}", width, height)))
+}
+
+/// 图片流的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// YUV4MPEG2 视频
+    Y4m,
+    /// 首尾相接的 PPM 或 PGM
+    Pnm,
+    /// 首尾相接的 PNG
+    Png,
+    /// 每个 PNG 之前为 4 字节大端序的长度
+    LengthPng,
+}
+impl StreamFormat {
+    /// 由开头的字节判断格式
+    /// # Examples
+    /// ```
+    /// use timg::StreamFormat;
+    /// assert_eq!(StreamFormat::detect(b"YUV4MPEG2 W2"), StreamFormat::Y4m);
+    /// assert_eq!(StreamFormat::detect(b"P6\n2 2"), StreamFormat::Pnm);
+    /// assert_eq!(StreamFormat::detect(b"\x89PNG\r\n\x1a\n"), StreamFormat::Png);
+    /// assert_eq!(StreamFormat::detect(b"\0\0\x01\0"), StreamFormat::LengthPng);
+    /// ```
+    pub fn detect(head: &[u8]) -> Self {
+        if head.starts_with(b"YUV4MPEG2") {
+            Self::Y4m
+        } else if head.starts_with(b"P5") || head.starts_with(b"P6") {
+            Self::Pnm
+        } else if head.starts_with(PNG_SIGNATURE) {
+            Self::Png
+        } else {
+            Self::LengthPng
+        }
+    }
+}
+
+/// Y4M 的流头部
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Y4mHeader {
+    width: SizeType,
+    height: SizeType,
+    /// 色度平面在两个方向上的下采样倍数, 为 `None` 时没有色度
+    chroma: Option<(SizeType, SizeType)>,
+}
+
+/// Reads the frames of an image stream one by one.
+pub struct FrameReader<R: Read> {
+    input: BufReader<R>,
+    format: StreamFormat,
+    y4m: Option<Y4mHeader>,
+}
+impl<R: Read> FrameReader<R> {
+    /// 读取足够的开头以判断格式
+    pub fn new(input: R) -> io::Result<Self> {
+        let mut input = BufReader::new(input);
+        let format = StreamFormat::detect(input.fill_buf()?);
+        let mut res = Self { input, format, y4m: None };
+        if format == StreamFormat::Y4m {
+            res.y4m = Some(res.read_y4m_header()?);
+        }
+        Ok(res)
+    }
+    pub fn format(&self) -> StreamFormat {
+        self.format
+    }
+    /// 读取 `len` 字节追加到 `buf`, 随着读入的数据增长,
+    /// 而不是预先按照不可信的长度分配
+    fn read_len(&mut self, len: usize, buf: &mut Vec<u8>) -> io::Result<()> {
+        let n = (&mut self.input).take(len as u64).read_to_end(buf)?;
+        if n < len {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+    /// 读取一行, 不包括换行符, 在流的末尾时返回 `None`
+    fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut line = Vec::new();
+        if self.input.read_until(b'\n', &mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.pop() != Some(b'\n') {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        Ok(Some(line))
+    }
+    fn read_y4m_header(&mut self) -> io::Result<Y4mHeader> {
+        let line = self.read_line()?.ok_or(ErrorKind::UnexpectedEof)?;
+        let line = String::from_utf8_lossy(&line);
+        let (mut width, mut height) = (0, 0);
+        let mut chroma = Some((2, 2));
+        for param in line.split(' ').skip(1) {
+            let (key, value) = param.split_at(param.len().min(1));
+            match key {
+                "W" => width = value.parse().unwrap_or(0),
+                "H" => height = value.parse().unwrap_or(0),
+                "C" => chroma = match value {
+                    // 仅支持 8 位, `420p10` 等高位深的格式每个值有两个字节
+                    "420" | "420jpeg" | "420paldv" | "420mpeg2" => Some((2, 2)),
+                    "422" => Some((2, 1)),
+                    "444" => Some((1, 1)),
+                    "mono" => None,
+                    x => return Err(invalid_data(
+                            format!("unsupported y4m colorspace {:?}", x))),
+                },
+                _ => (),
+            }
+        }
+        if width == 0 || height == 0 {
+            return Err(invalid_data(format!("invalid y4m header {:?}", line)));
+        }
+        Ok(Y4mHeader { width, height, chroma })
+    }
+    fn read_y4m_frame(&mut self, header: Y4mHeader) -> io::Result<Option<DynamicImage>> {
+        let Some(line) = self.read_line()? else { return Ok(None) };
+        if ! line.starts_with(b"FRAME") {
+            return Err(invalid_data("invalid y4m frame header".to_string()));
+        }
+        let Y4mHeader { width, height, chroma } = header;
+        let mut luma = vec![0; frame_len(width, height, 1)?];
+        self.input.read_exact(&mut luma)?;
+        let Some((sx, sy)) = chroma else {
+            return Ok(Some(DynamicImage::ImageLuma8(
+                        GrayImage::from_raw(width, height, luma).unwrap())));
+        };
+        let chroma_width = width.div_ceil(sx);
+        let chroma_len = frame_len(chroma_width, height.div_ceil(sy), 1)?;
+        let mut planes = vec![0; chroma_len * 2];
+        self.input.read_exact(&mut planes)?;
+        let (u, v) = planes.split_at(chroma_len);
+        Ok(Some(DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+            let idx = ((y / sy) * chroma_width + x / sx) as usize;
+            image::Rgb(yuv_to_rgb(luma[(y * width + x) as usize], u[idx], v[idx]))
+        }))))
+    }
+    /// 读取 PNM 头部中的一个数字, 跳过空白与注释
+    fn read_pnm_number(&mut self) -> io::Result<u32> {
+        let mut digits = String::new();
+        loop {
+            let mut byte = [0];
+            self.input.read_exact(&mut byte)?;
+            match byte[0] {
+                b'#' if digits.is_empty() => {
+                    self.read_line()?;
+                },
+                x if x.is_ascii_whitespace() => if ! digits.is_empty() {
+                    break;
+                },
+                x if x.is_ascii_digit() => digits.push(x as char),
+                x => return Err(invalid_data(
+                        format!("invalid pnm header byte {:?}", x as char))),
+            }
+        }
+        digits.parse().map_err(|_| invalid_data("pnm number overflow".to_string()))
+    }
+    fn read_pnm_frame(&mut self) -> io::Result<Option<DynamicImage>> {
+        // 跳过帧之间的空白
+        loop {
+            let buf = self.input.fill_buf()?;
+            match buf.iter().position(|x| ! x.is_ascii_whitespace()) {
+                Some(n) => {
+                    self.input.consume(n);
+                    break;
+                },
+                None if buf.is_empty() => return Ok(None),
+                None => {
+                    let len = buf.len();
+                    self.input.consume(len);
+                },
+            }
+        }
+        let mut magic = [0; 2];
+        self.input.read_exact(&mut magic)?;
+        let channels = match &magic {
+            b"P5" => 1,
+            b"P6" => 3,
+            _ => return Err(invalid_data("unsupported pnm frame".to_string())),
+        };
+        let width = self.read_pnm_number()?;
+        let height = self.read_pnm_number()?;
+        let max = self.read_pnm_number()?;
+        if width == 0 || height == 0 || max == 0 || max > 0xffff {
+            return Err(invalid_data("invalid pnm header".to_string()));
+        }
+        let bytes = if max < 256 { 1 } else { 2 };
+        let len = frame_len(width, height, channels * bytes)?;
+        if max < 256 {
+            let mut data = vec![0; len];
+            self.input.read_exact(&mut data)?;
+            let scale = |x: u8| (x as u32 * 255 / max).min(255) as u8;
+            if max != 255 {
+                data.iter_mut().for_each(|x| *x = scale(*x));
+            }
+            return Ok(Some(if channels == 1 {
+                DynamicImage::ImageLuma8(ImageBuffer::from_raw(width, height, data).unwrap())
+            } else {
+                DynamicImage::ImageRgb8(ImageBuffer::from_raw(width, height, data).unwrap())
+            }));
+        }
+        let mut data = vec![0; len];
+        self.input.read_exact(&mut data)?;
+        let data: Vec<u16> = data.chunks_exact(2)
+            .map(|x| (u16::from_be_bytes([x[0], x[1]]) as u32 * 0xffff / max)
+                 .min(0xffff) as u16)
+            .collect();
+        Ok(Some(if channels == 1 {
+            DynamicImage::ImageLuma16(ImageBuffer::from_raw(width, height, data).unwrap())
+        } else {
+            DynamicImage::ImageRgb16(ImageBuffer::from_raw(width, height, data).unwrap())
+        }))
+    }
+    /// 读取一个完整的 PNG, 直到 IEND 块
+    fn read_png(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if self.input.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        let mut data = vec![0; PNG_SIGNATURE.len()];
+        self.input.read_exact(&mut data)?;
+        if data != PNG_SIGNATURE {
+            return Err(invalid_data("invalid png signature".to_string()));
+        }
+        loop {
+            // 长度, 类型, 数据, CRC
+            let mut head = [0; 8];
+            self.input.read_exact(&mut head)?;
+            let len = u32::from_be_bytes([head[0], head[1], head[2], head[3]]) as usize;
+            data.extend(head);
+            self.read_len(len + 4, &mut data)?;
+            if &head[4..] == b"IEND" {
+                return Ok(Some(data));
+            }
+        }
+    }
+    fn read_length_png(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if self.input.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        let mut len = [0; 4];
+        self.input.read_exact(&mut len)?;
+        let mut data = Vec::new();
+        self.read_len(u32::from_be_bytes(len) as usize, &mut data)?;
+        Ok(Some(data))
+    }
+    /// 读取下一帧, 流结束时返回 `None`
+    pub fn next_frame(&mut self) -> io::Result<Option<DynamicImage>> {
+        let png = match self.format {
+            StreamFormat::Y4m => return self.read_y4m_frame(self.y4m.unwrap()),
+            StreamFormat::Pnm => return self.read_pnm_frame(),
+            StreamFormat::Png => self.read_png()?,
+            StreamFormat::LengthPng => self.read_length_png()?,
+        };
+        png.map(|data| image::load_from_memory_with_format(&data, ImageFormat::Png)
+                .map_err(|e| invalid_data(e.to_string())))
+            .transpose()
+    }
+}
+impl<R: Read> Iterator for FrameReader<R> {
+    type Item = io::Result<DynamicImage>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_frame().transpose()
+    }
+}
+
+#[test]
+fn frame_reader_test() {
+    use std::io::Cursor;
+
+    let frames = |data: Vec<u8>| {
+        let reader = FrameReader::new(Cursor::new(data)).unwrap();
+        let format = reader.format();
+        (format, reader.collect::<io::Result<Vec<_>>>().unwrap())
+    };
+
+    // 4:2:0 的 2x2 帧, 每帧为 4 字节 Y 与各 1 字节的 U V
+    let mut y4m = b"YUV4MPEG2 W2 H2 F25:1 C420jpeg\n".to_vec();
+    for luma in [16, 235] {
+        y4m.extend(b"FRAME\n");
+        y4m.extend([luma, luma, luma, luma, 128, 128]);
+    }
+    let (format, res) = frames(y4m);
+    assert_eq!(format, StreamFormat::Y4m);
+    assert_eq!(res.len(), 2);
+    assert_eq!(res[0].to_rgb8().get_pixel(1, 1).0, [0; 3]);
+    assert_eq!(res[1].to_rgb8().get_pixel(1, 1).0, [255; 3]);
+
+    let mut pnm = b"P6 1 1 255\n\x01\x02\x03\n".to_vec();
+    pnm.extend(b"P5\n# comment\n2 1\n65535\n\x12\x34\xff\xff");
+    let (format, res) = frames(pnm);
+    assert_eq!(format, StreamFormat::Pnm);
+    assert_eq!(res[0].to_rgb8().get_pixel(0, 0).0, [1, 2, 3]);
+    assert_eq!(res[1].as_luma16().unwrap().get_pixel(0, 0).0, [0x1234]);
+
+    let mut png = Vec::new();
+    DynamicImage::ImageRgb8(RgbImage::from_pixel(3, 2, image::Rgb([9, 8, 7])))
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png).unwrap();
+    let (format, res) = frames([png.clone(), png.clone()].concat());
+    assert_eq!(format, StreamFormat::Png);
+    assert_eq!(res.len(), 2);
+    assert_eq!(res[1].to_rgb8().get_pixel(2, 1).0, [9, 8, 7]);
+
+    let mut data = (png.len() as u32).to_be_bytes().to_vec();
+    data.extend(&png);
+    let (format, res) = frames(data);
+    assert_eq!(format, StreamFormat::LengthPng);
+    assert_eq!((res[0].width(), res[0].height()), (3, 2));
+
+    // 过大或被截断的帧返回错误, 而不是溢出或预先分配
+    let next_err = |data: &[u8]| {
+        FrameReader::new(Cursor::new(data.to_vec())).unwrap()
+            .next_frame().unwrap_err().kind()
+    };
+    assert_eq!(next_err(b"YUV4MPEG2 W70000 H70000\nFRAME\n"), ErrorKind::InvalidData);
+    // 高位深的 4:2:0
+    for header in [&b"YUV4MPEG2 W2 H2 C420p10\n"[..], b"YUV4MPEG2 W2 H2 C420p16\n"] {
+        let err = FrameReader::new(Cursor::new(header.to_vec())).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+    assert_eq!(next_err(b"P6 70000 70000 255\n"), ErrorKind::InvalidData);
+    assert_eq!(next_err(b"\xff\xff\xff\xff\x89PNG"), ErrorKind::UnexpectedEof);
+    assert_eq!(next_err(&[PNG_SIGNATURE, b"\x7f\xff\xff\xffIHDR"].concat()),
+               ErrorKind::UnexpectedEof);
+}
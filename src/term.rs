@@ -0,0 +1,225 @@
+use std::{
+    collections::VecDeque,
+    io::{
+        self,
+        Read,
+        Write,
+    },
+    os::unix::io::RawFd,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use term_lattice::types::Rgb;
+
+use super::*;
+
+/// 查询终端时等待回复的默认时长
+pub const QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Wait until `fd` is readable.
+/// `None` timeout will block until readable.
+pub fn poll_read(fd: RawFd, timeout: Option<Duration>) -> bool {
+    let mut fds = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+    let timeout = timeout.map_or(-1, |t| t.as_millis().min(i32::MAX as u128) as i32);
+    let res = unsafe { libc::poll(&mut fds, 1, timeout) };
+    res > 0 && fds.revents & libc::POLLIN != 0
+}
+
+/// Terminal input that keeps the keys typed while waiting for a reply.
+///
+/// Reading returns the kept keys first,
+/// and [`Self::poll`] is ready while any key is kept.
+pub struct TermInput<R> {
+    input: R,
+    fd: RawFd,
+    /// 查询时读到的, 不属于回复的输入
+    pending: VecDeque<u8>,
+}
+impl<R: Read> TermInput<R> {
+    pub fn new(input: R, fd: RawFd) -> Self {
+        Self { input, fd, pending: VecDeque::new() }
+    }
+    pub fn fd(&self) -> RawFd {
+        self.fd
+    }
+    /// 等待直到有输入可读, 见 [`poll_read`]
+    pub fn poll(&self, timeout: Option<Duration>) -> bool {
+        ! self.pending.is_empty() || poll_read(self.fd, timeout)
+    }
+}
+impl<R: Read> Read for TermInput<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            self.input.read(buf)
+        } else {
+            self.pending.read(buf)
+        }
+    }
+}
+
+/// 将查询时读到的字节分为终端的回复与用户的输入
+///
+/// 回复为 `CSI ... t`, `CSI ... c` 与以 BEL 或 ST 结尾的 OSC,
+/// 其它的字节, 包括方向键等序列, 都是用户的输入
+/// # Examples
+/// ```
+/// use timg::split_reply;
+/// let (reply, keys) = split_reply(b"j\x1b[6;20;10tk\x1b[A\x1b]11;rgb:0/0/0\x07q");
+/// assert_eq!(reply, b"\x1b[6;20;10t\x1b]11;rgb:0/0/0\x07");
+/// assert_eq!(keys, b"jk\x1b[Aq");
+/// ```
+pub fn split_reply(bytes: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let (mut reply, mut keys) = (Vec::new(), Vec::new());
+    let mut rest = bytes;
+    while let Some((&first, tail)) = rest.split_first() {
+        let len = match (first, tail.first()) {
+            // 参数与中间字节之后的结束字节
+            (b'\x1b', Some(b'[')) => tail[1..].iter()
+                .position(|x| (0x40..=0x7e).contains(x))
+                .map(|i| i + 3)
+                .filter(|&len| matches!(rest[len - 1], b't' | b'c')),
+            (b'\x1b', Some(b']')) => rest.iter().enumerate().skip(2)
+                .find_map(|(i, &x)| match x {
+                    b'\x07' => Some(i + 1),
+                    b'\x1b' => (rest.get(i + 1) == Some(&b'\\')).then_some(i + 2),
+                    _ => None,
+                }),
+            _ => None,
+        };
+        match len {
+            Some(len) => {
+                reply.extend(&rest[..len]);
+                rest = &rest[len..];
+            },
+            None => {
+                keys.push(first);
+                rest = tail;
+            },
+        }
+    }
+    (reply, keys)
+}
+
+/// Send `request` to the terminal, and read the reply.
+///
+/// A primary device attributes request (`CSI c`) is sent after it,
+/// which is answered by almost all terminals, so we know when to stop
+/// reading without waiting for the timeout.
+/// The keys typed in the meantime are kept in `input`.
+pub fn query<R: Read>(input: &mut TermInput<R>, request: &str,
+                      timeout: Duration) -> String {
+    let mut res: Vec<u8> = Vec::new();
+    let mut stderr = io::stderr();
+    if write!(stderr, "{}\x1b[c", request).and_then(|_| stderr.flush()).is_err() {
+        return String::new();
+    }
+    let start = Instant::now();
+    let mut buf: [u8; 1] = [0];
+    loop {
+        let elapsed = start.elapsed();
+        if elapsed >= timeout
+            || ! poll_read(input.fd, Some(timeout - elapsed))
+            || input.input.read_exact(&mut buf).is_err() {
+            break;
+        }
+        res.push(buf[0]);
+        if res.ends_with(b"c") {
+            // 设备属性的回复 `CSI ? ... c`, 查询结束
+            if let Some(i) = res.windows(3).rposition(|s| s == b"\x1b[?") {
+                res.truncate(i);
+                break;
+            }
+        }
+    }
+    let (reply, keys) = split_reply(&res);
+    input.pending.extend(keys);
+    String::from_utf8_lossy(&reply).into_owned()
+}
+
+/// 从终端回复中查找 `CSI {kind} ; {height} ; {width} t`
+/// # Examples
+/// ```
+/// use timg::{parse_size_report, Position};
+/// let s = "\x1b[4;600;800t\x1b[6;20;10t";
+/// assert_eq!(parse_size_report(s, 6), Some(Position::new(10, 20)));
+/// assert_eq!(parse_size_report(s, 4), Some(Position::new(800, 600)));
+/// assert_eq!(parse_size_report(s, 8), None);
+/// ```
+pub fn parse_size_report(s: &str, kind: u32) -> Option<Position> {
+    s.split('\x1b').find_map(|seq| {
+        let body = seq.strip_prefix('[')?.strip_suffix('t')?;
+        let nums = body.split(';')
+            .map(|n| n.parse::<SizeType>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        match nums[..] {
+            [k, h, w] if k == kind && w != 0 && h != 0
+                => Some(Position::new(w, h)),
+            _ => None,
+        }
+    })
+}
+
+/// 使用 `TIOCGWINSZ` 获取 (列数, 行数) 及 (像素宽, 像素高)
+pub fn get_winsize(fd: RawFd) -> Option<(Position, Position)> {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) } != 0 {
+        return None;
+    }
+    Some((Position::new(size.ws_col.into(), size.ws_row.into()),
+          Position::new(size.ws_xpixel.into(), size.ws_ypixel.into())))
+}
+
+/// 通过 `TIOCGWINSZ` 的像素字段计算单个字符单元的像素大小
+pub fn get_winsize_cell_size(fd: RawFd) -> Option<Position> {
+    let (cells, pixels) = get_winsize(fd)?;
+    if cells.x == 0 || cells.y == 0 || pixels.x == 0 || pixels.y == 0 {
+        return None;
+    }
+    Some(pixels / cells)
+}
+
+/// Get the size of a character cell in pixels.
+///
+/// Try `CSI 16 t` first, then `CSI 14 t` divided by the number of cells,
+/// and finally the pixel fields of `TIOCGWINSZ`.
+pub fn get_cell_size<R: Read>(input: &mut TermInput<R>) -> Option<Position> {
+    let fd = input.fd();
+    let reply = query(input, "\x1b[16t\x1b[14t", QUERY_TIMEOUT);
+    if let Some(size) = parse_size_report(&reply, 6) {
+        return Some(size);
+    }
+    let winsize = get_winsize(fd).or_else(|| get_winsize(libc::STDERR_FILENO));
+    if let (Some(pixels), Some((cells, _)))
+        = (parse_size_report(&reply, 4), winsize) {
+        if cells.x != 0 && cells.y != 0 {
+            return Some(pixels / cells);
+        }
+    }
+    get_winsize_cell_size(fd)
+        .or_else(|| get_winsize_cell_size(libc::STDERR_FILENO))
+}
+
+/// 从终端回复中查找 `OSC 11 ; rgb:r/g/b`, 以 BEL 或 ST 结尾
+/// # Examples
+/// ```
+/// use timg::parse_background_report;
+/// assert_eq!(parse_background_report("\x1b]11;rgb:ffff/0000/8080\x07"),
+///            Some([0xff, 0x00, 0x80]));
+/// assert_eq!(parse_background_report("\x1b]11;rgb:28/2c/34\x1b\\"),
+///            Some([0x28, 0x2c, 0x34]));
+/// assert_eq!(parse_background_report(""), None);
+/// ```
+pub fn parse_background_report(s: &str) -> Option<Rgb> {
+    let start = s.find("\x1b]11;")? + "\x1b]11;".len();
+    let body = &s[start..];
+    let end = body.find(['\x07', '\x1b']).unwrap_or(body.len());
+    parse_x_color(&body[..end])
+}
+
+/// Query the background color of the terminal with OSC 11.
+pub fn get_terminal_background<R: Read>(input: &mut TermInput<R>) -> Option<Rgb> {
+    parse_background_report(&query(input, "\x1b]11;?\x07", QUERY_TIMEOUT))
+}
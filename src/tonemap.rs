@@ -0,0 +1,220 @@
+use image::{
+    DynamicImage,
+    RgbaImage,
+};
+
+use super::*;
+
+/// 色调映射算子
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToneMap {
+    /// 直接截断超出范围的值
+    Clamp,
+    Reinhard,
+    /// ACES filmic 曲线的近似
+    Aces,
+}
+impl ToneMap {
+    pub const ALL: [Self; 3] = [Self::Clamp, Self::Reinhard, Self::Aces];
+    /// # Examples
+    /// ```
+    /// use timg::ToneMap;
+    /// assert_eq!(ToneMap::Clamp.next(), ToneMap::Reinhard);
+    /// assert_eq!(ToneMap::Aces.next(), ToneMap::Clamp);
+    /// ```
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|&x| x == self).unwrap();
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Clamp => "Clamp",
+            Self::Reinhard => "Reinhard",
+            Self::Aces => "ACES",
+        }
+    }
+    /// 映射线性光强, 结果在 0 到 1 之间
+    /// # Examples
+    /// ```
+    /// use timg::ToneMap;
+    /// assert_eq!(ToneMap::Clamp.map(4.0), 1.0);
+    /// assert_eq!(ToneMap::Reinhard.map(1.0), 0.5);
+    /// assert!(ToneMap::Aces.map(100.0) > 0.99);
+    /// ```
+    pub fn map(&self, x: f32) -> f32 {
+        let x = x.max(0.0);
+        match self {
+            Self::Clamp => x,
+            Self::Reinhard => x / (1.0 + x),
+            Self::Aces => {
+                // Narkowicz 的拟合
+                let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                (x * (a * x + b)) / (x * (c * x + d) + e)
+            },
+        }.clamp(0.0, 1.0)
+    }
+}
+
+/// Exposure, offset and tonemapping of high bit-depth images.
+///
+/// Works on the full precision values in linear light.
+/// Integer images are sRGB encoded, float images are linear already
+/// and may exceed 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMapping {
+    /// 曝光, 单位为档
+    pub exposure: Float,
+    /// 在曝光之后加在线性光强上
+    pub offset: Float,
+    pub operator: ToneMap,
+}
+impl ToneMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// 是否与直接转换为 8 位相同, 仅对整数图片成立
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+    /// 调整曝光 `steps` 个 1/4 档
+    pub fn step_exposure(&mut self, steps: i32) {
+        self.exposure = (self.exposure + steps as Float * 0.25).clamp(-16.0, 16.0);
+    }
+    /// 调整偏移 `steps` 个 0.005
+    /// # Examples
+    /// ```
+    /// use timg::ToneMapping;
+    /// let mut tm = ToneMapping::new();
+    /// tm.step_offset(3);
+    /// tm.step_offset(-3);
+    /// assert!(tm.is_identity());
+    /// ```
+    pub fn step_offset(&mut self, steps: i32) {
+        self.offset = (self.offset + steps as Float * 0.005).clamp(-1.0, 1.0);
+        if self.offset.abs() < 1e-9 {
+            self.offset = 0.0
+        }
+    }
+    /// 状态行中的格式
+    /// # Examples
+    /// ```
+    /// use timg::ToneMapping;
+    /// let tm = ToneMapping { exposure: 1.5, ..ToneMapping::new() };
+    /// assert_eq!(tm.fmt_status(), "EV+1.50 Off+0.000 Clamp");
+    /// ```
+    pub fn fmt_status(&self) -> String {
+        format!("EV{:+.2} Off{:+.3} {}",
+                self.exposure, self.offset, self.operator.name())
+    }
+    /// 曝光对线性光强的倍数
+    fn gain(&self) -> f32 {
+        (2.0 as Float).powf(self.exposure) as f32
+    }
+    fn map_gain(&self, linear: f32, gain: f32) -> u8 {
+        let linear = linear.max(0.0) * gain + self.offset as f32;
+        linear_to_srgb_u8(self.operator.map(linear))
+    }
+    /// 映射一个线性光强的值为 sRGB 编码的 8 位值
+    pub fn map(&self, linear: f32) -> u8 {
+        self.map_gain(linear, self.gain())
+    }
+    /// 映射图片为 8 位, 透明度不变
+    pub fn apply(&self, img: &DynamicImage) -> RgbaImage {
+        let is_float = is_float_image(img);
+        let rgba = img.to_rgba32f();
+        let gain = self.gain();
+        let mut res = RgbaImage::new(rgba.width(), rgba.height());
+        let row_len = rgba.width() as usize * 4;
+        par_rows(&mut res, available_threads(), |first, band| {
+            let src = &rgba.as_raw()[first as usize * row_len..];
+            for (pixel, src) in band.pixels_mut().zip(src.chunks_exact(4)) {
+                for (out, &value) in pixel.0.iter_mut().zip(&src[..3]) {
+                    let linear = if is_float { value } else { srgb_to_linear(value.max(0.0)) };
+                    *out = self.map_gain(linear, gain);
+                }
+                pixel.0[3] = (src[3].clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        });
+        res
+    }
+}
+impl Default for ToneMapping {
+    fn default() -> Self {
+        Self { exposure: 0.0, offset: 0.0, operator: ToneMap::Clamp }
+    }
+}
+
+/// 转换为用于显示的 8 位图片, 浮点图片的线性光强编码为 sRGB
+pub fn to_display_rgba8(img: &DynamicImage) -> RgbaImage {
+    if is_float_image(img) {
+        ToneMapping::new().apply(img)
+    } else {
+        img.to_rgba8()
+    }
+}
+
+/// 以原始的精度格式化像素的值
+/// # Examples
+/// ```
+/// # use image::{DynamicImage, ImageBuffer, Rgb};
+/// use timg::fmt_pixel;
+/// let img = DynamicImage::ImageRgb16(ImageBuffer::from_pixel(1, 1, Rgb([1, 2, 65535])));
+/// assert_eq!(fmt_pixel(&img, 0, 0), "(1,2,65535)");
+/// let img = DynamicImage::ImageRgb32F(ImageBuffer::from_pixel(1, 1, Rgb([1.5, 0.0, 0.25])));
+/// assert_eq!(fmt_pixel(&img, 0, 0), "(1.5000,0.0000,0.2500)");
+/// ```
+pub fn fmt_pixel(img: &DynamicImage, x: SizeType, y: SizeType) -> String {
+    macro_rules! fmt {
+        ( $img:expr, $fmt:literal ) => {
+            format!("({})", $img.get_pixel(x, y).0.iter()
+                    .map(|n| format!($fmt, n))
+                    .collect::<Vec<_>>().join(","))
+        };
+    }
+    match img {
+        DynamicImage::ImageLuma8(img) => fmt!(img, "{}"),
+        DynamicImage::ImageLumaA8(img) => fmt!(img, "{}"),
+        DynamicImage::ImageRgb8(img) => fmt!(img, "{}"),
+        DynamicImage::ImageRgba8(img) => fmt!(img, "{}"),
+        DynamicImage::ImageLuma16(img) => fmt!(img, "{}"),
+        DynamicImage::ImageLumaA16(img) => fmt!(img, "{}"),
+        DynamicImage::ImageRgb16(img) => fmt!(img, "{}"),
+        DynamicImage::ImageRgba16(img) => fmt!(img, "{}"),
+        DynamicImage::ImageRgb32F(img) => fmt!(img, "{:.4}"),
+        DynamicImage::ImageRgba32F(img) => fmt!(img, "{:.4}"),
+        img => fmt!(img.to_rgba32f(), "{:.4}"),
+    }
+}
+
+#[test]
+fn tone_mapping_test() {
+    use image::{ImageBuffer, Rgb};
+
+    // 恒等的映射与直接转换为 8 位一致
+    let img = DynamicImage::ImageRgb16(ImageBuffer::from_fn(16, 16, |x, y| {
+        Rgb([(x * 4096) as u16, (y * 4096) as u16, 65535])
+    }));
+    let tm = ToneMapping::new();
+    assert_eq!(tm.apply(&img), img.to_rgba8());
+
+    // 提高一档曝光后, 线性光强加倍
+    let mut tm = ToneMapping::new();
+    tm.step_exposure(4);
+    assert_eq!(tm.exposure, 1.0);
+    assert_eq!(tm.map(0.25), linear_to_srgb_u8(0.5));
+
+    // 超出范围的浮点值在色调映射后仍然可以区分
+    let img = DynamicImage::ImageRgb32F(ImageBuffer::from_fn(2, 1, |x, _| {
+        Rgb([[2.0, 8.0][x as usize]; 3])
+    }));
+    let tm = ToneMapping { operator: ToneMap::Reinhard, ..ToneMapping::new() };
+    let res = tm.apply(&img);
+    assert!(res.get_pixel(0, 0).0[0] < res.get_pixel(1, 0).0[0]);
+    assert!(ToneMapping::new().apply(&img).pixels().all(|x| x.0 == [255; 4]));
+    // 浮点图片的值为线性光强, 不再经过 sRGB 的解码
+    let img = DynamicImage::ImageRgb32F(ImageBuffer::from_pixel(40, 40, Rgb([0.5; 3])));
+    let res = tm.apply(&img);
+    let v = linear_to_srgb_u8(0.5 / 1.5);
+    assert_eq!(res.get_pixel(39, 39).0, [v, v, v, 255]);
+    assert_eq!(to_display_rgba8(&img).get_pixel(0, 0).0[0], linear_to_srgb_u8(0.5));
+}
@@ -0,0 +1,131 @@
+use std::{
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::SystemTime,
+};
+
+/// Polls the modification time and length of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileWatcher {
+    path: PathBuf,
+    /// 上次检查时的修改时间与长度, 文件不存在时为 `None`
+    state: Option<(SystemTime, u64)>,
+}
+impl FileWatcher {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let state = Self::read_state(&path);
+        Self { path, state }
+    }
+    fn read_state(path: &Path) -> Option<(SystemTime, u64)> {
+        let meta = fs::metadata(path).ok()?;
+        Some((meta.modified().ok()?, meta.len()))
+    }
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// 文件是否在上次检查之后被修改, 文件被删除时不算作修改
+    pub fn changed(&mut self) -> bool {
+        let state = Self::read_state(&self.path);
+        if state == self.state {
+            return false;
+        }
+        self.state = state;
+        state.is_some()
+    }
+}
+
+/// Polls a directory for its newest file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirWatcher {
+    dir: PathBuf,
+    /// 当前最新的文件, 同时检查它自身的修改
+    newest: Option<FileWatcher>,
+}
+impl DirWatcher {
+    /// 在首次检查之前, 没有最新的文件
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self { dir: dir.as_ref().to_path_buf(), newest: None }
+    }
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+    pub fn newest(&self) -> Option<&Path> {
+        self.newest.as_ref().map(FileWatcher::path)
+    }
+    /// 修改时间最新的文件, 时间相同时取名字较大的
+    fn scan<F>(&self, filter: F) -> Option<PathBuf>
+    where F: Fn(&Path) -> bool,
+    {
+        fs::read_dir(&self.dir).ok()?
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let meta = entry.metadata().ok()?;
+                let path = entry.path();
+                (meta.is_file() && filter(&path))
+                    .then_some((meta.modified().ok()?, path))
+            })
+            .max()
+            .map(|(_, path)| path)
+    }
+    /// 最新的文件是否在上次检查之后改变, 或者它被修改了,
+    /// 只有 `filter` 为真的文件会被考虑
+    pub fn changed<F>(&mut self, filter: F) -> bool
+    where F: Fn(&Path) -> bool,
+    {
+        let Some(path) = self.scan(filter) else { return false };
+        match &mut self.newest {
+            Some(newest) if newest.path() == path => newest.changed(),
+            _ => {
+                self.newest = Some(FileWatcher::new(path));
+                true
+            },
+        }
+    }
+}
+
+#[test]
+fn file_watcher_test() {
+    let path = std::env::temp_dir()
+        .join(format!("timg-watch-test-{}", std::process::id()));
+    fs::write(&path, b"a").unwrap();
+    let mut watcher = FileWatcher::new(&path);
+    assert!(! watcher.changed());
+    fs::write(&path, b"ab").unwrap();
+    assert!(watcher.changed());
+    assert!(! watcher.changed());
+    fs::remove_file(&path).unwrap();
+    assert!(! watcher.changed());
+    fs::write(&path, b"a").unwrap();
+    assert!(watcher.changed());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn dir_watcher_test() {
+    let dir = std::env::temp_dir()
+        .join(format!("timg-dir-watch-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let is_png = |path: &Path| path.extension()
+        .is_some_and(|ext| ext == "png");
+    let mut watcher = DirWatcher::new(&dir);
+    assert!(! watcher.changed(is_png));
+    assert_eq!(watcher.newest(), None);
+    fs::write(dir.join("a.png"), b"a").unwrap();
+    fs::write(dir.join("b.txt"), b"b").unwrap();
+    assert!(watcher.changed(is_png));
+    assert_eq!(watcher.newest(), Some(&*dir.join("a.png")));
+    assert!(! watcher.changed(is_png));
+    // 修改时间的精度可能较低
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(dir.join("c.png"), b"c").unwrap();
+    assert!(watcher.changed(is_png));
+    assert_eq!(watcher.newest(), Some(&*dir.join("c.png")));
+    fs::write(dir.join("c.png"), b"cc").unwrap();
+    assert!(watcher.changed(is_png));
+    assert!(! watcher.changed(is_png));
+    fs::remove_dir_all(&dir).unwrap();
+}